@@ -0,0 +1,23 @@
+# A span of time left, as in "2 hours 10 minutes".
+time-left = { $hours ->
+        [0] { $minutes ->
+                [one] { $minutes } minute
+               *[other] { $minutes } minutes
+            }
+        [one] { $hours } hour { $minutes ->
+                [one] { $minutes } minute
+               *[other] { $minutes } minutes
+            }
+       *[other] { $hours } hours { $minutes ->
+                [one] { $minutes } minute
+               *[other] { $minutes } minutes
+            }
+    }
+
+# Under the rows of the main window, with the time told by `time-left`.
+remaining-in-waqt = { $time } remaining
+remaining-of-waqt = { $time } remaining of { $prayer }
+remaining-to-waqt = { $time } remaining to { $prayer }
+
+# Spoken at the start of a waqt.
+announce-waqt-start = It is now time for { $prayer }, { $time } until { $next }
@@ -0,0 +1,83 @@
+//! The Fajr alarm, which rings louder and louder until it is dismissed.
+
+use adhaan::Prayer;
+use chrono::{DateTime, Duration, Local, Utc};
+use druid::Data;
+
+use crate::{
+    alerts::{Alert, AlertKind},
+    app_state::AppState,
+};
+
+#[derive(Debug, Clone, PartialEq, Data)]
+pub struct Alarm {
+    /// When it started ringing, to escalate the volume from.
+    #[data(same_fn = "PartialEq::eq")]
+    pub since: DateTime<Utc>,
+    /// Operands of the sum to solve before dismissing.
+    pub puzzle: Option<(u8, u8)>,
+    pub answer: String,
+}
+
+/// Fraction of the full volume the alarm starts ringing at.
+const START_VOLUME: f32 = 0.2;
+/// Minutes to reach the full volume in.
+const ESCALATION_MINUTES: f32 = 3.0;
+
+impl Alarm {
+    pub fn new(now: DateTime<Utc>, puzzle: bool) -> Alarm {
+        // Not random, but unpredictable enough for someone half asleep.
+        let seed = now.timestamp_subsec_nanos() / 1000;
+        Alarm {
+            since: now,
+            puzzle: puzzle.then(|| (10 + (seed % 40) as u8, 10 + (seed / 40 % 40) as u8)),
+            answer: String::new(),
+        }
+    }
+
+    pub fn volume(&self, now: DateTime<Utc>) -> f32 {
+        let minutes = (now - self.since).num_seconds() as f32 / 60.0;
+        (START_VOLUME + (1.0 - START_VOLUME) * minutes / ESCALATION_MINUTES).min(1.0)
+    }
+
+    pub fn can_dismiss(&self) -> bool {
+        self.puzzle.map_or(true, |(a, b)| {
+            self.answer.trim().parse::<u16>() == Ok(a as u16 + b as u16)
+        })
+    }
+}
+
+/// The end of the snooze starting at `now`, unless it would pass sunrise.
+pub fn snooze_until(data: &AppState, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+    let until = now + Duration::minutes(data.config.fajr_alarm.snooze as _);
+    (until < data.adjusted_time_of(Prayer::Sunrise)).then(|| until)
+}
+
+pub fn alerts(data: &AppState) -> Vec<Alert> {
+    if !data.config.fajr_alarm.enabled {
+        return vec![];
+    }
+
+    let fajr = data.adjusted_time_of(Prayer::Fajr);
+    let at = fajr + Duration::minutes(data.config.fajr_alarm.offset as _);
+    let mut alerts = vec![];
+    if at < data.adjusted_time_of(Prayer::Sunrise) {
+        alerts.push(Alert {
+            key: format!("alarm/{}", fajr.with_timezone(&Local).date().naive_local()),
+            kind: AlertKind::Alarm,
+            at,
+            summary: "Fajr alarm".into(),
+            body: String::new(),
+        });
+    }
+    if let Some(until) = data.alarm_snoozed_until {
+        alerts.push(Alert {
+            key: format!("alarm/snooze/{}", until),
+            kind: AlertKind::Alarm,
+            at: until,
+            summary: "Fajr alarm".into(),
+            body: String::new(),
+        });
+    }
+    alerts
+}
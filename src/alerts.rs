@@ -0,0 +1,529 @@
+//! Scheduling of reminders.
+
+use std::collections::HashMap;
+
+use adhaan::Prayer;
+use chrono::{DateTime, Duration, Local, Timelike, Utc};
+use druid::{widget::prelude::*, LocalizedString, Selector, Target, TimerToken};
+
+use crate::{
+    alarm::{self, Alarm},
+    app_state::{AppState, TimeLeft, Waqt},
+    audio,
+    config::Sound,
+    events, fasting,
+    hooks::{self, HookEvent},
+    media,
+    notification::{self, Action},
+    presence, speech, ui_alarm, ui_main,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AlertKind {
+    /// Calendar reminders not tied to a waqt.
+    Reminder,
+    BeforeStart(Prayer, u16),
+    WaqtStart(Prayer),
+    Critical(Prayer),
+    /// Plays a sound instead of showing a notification.
+    Sound(Prayer, Sound),
+    /// Speaks the start of a waqt, with the label in the summary, the time
+    /// left of it and the label of the waqt after.
+    Announce(Prayer, TimeLeft, &'static str),
+    /// Rings the Fajr alarm until it is dismissed.
+    Alarm,
+    Hook(HookEvent, Waqt),
+    /// Brings up the main window.
+    Popup(Prayer),
+}
+
+impl AlertKind {
+    /// The prayer of the waqt the alert is about.
+    fn prayer(self) -> Option<Prayer> {
+        match self {
+            AlertKind::BeforeStart(prayer, _)
+            | AlertKind::WaqtStart(prayer)
+            | AlertKind::Critical(prayer)
+            | AlertKind::Sound(prayer, _)
+            | AlertKind::Announce(prayer, ..) => Some(prayer),
+            AlertKind::Reminder | AlertKind::Alarm | AlertKind::Hook(..) | AlertKind::Popup(_) => {
+                None
+            }
+        }
+    }
+
+    fn actions(self) -> Vec<Action> {
+        let mut actions = SNOOZE_MINUTES.map(Action::Snooze).to_vec();
+        if matches!(self.prayer(), Some(prayer) if prayer != Prayer::Sunrise) {
+            actions.push(Action::Prayed);
+        }
+        actions
+    }
+}
+
+const SNOOZE_MINUTES: [u16; 2] = [5, 10];
+
+/// Sent from the thread of a notification when one of its actions is chosen.
+const ALERT_ACTION: Selector<(Alert, Action)> = Selector::new("adhaan.alerts.action");
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Alert {
+    /// Identifies the alert across recalculations, so that it is raised only once.
+    pub key: String,
+    pub kind: AlertKind,
+    pub at: DateTime<Utc>,
+    pub summary: String,
+    pub body: String,
+}
+
+/// All the alerts derivable from the current state.
+pub fn scheduled(data: &AppState) -> Vec<Alert> {
+    let waqts = data.waqts_since_yesterday();
+    let mut alerts = vec![];
+    alerts.extend(before_start_alerts(data, &waqts));
+    alerts.extend(waqt_start_alerts(data, &waqts));
+    alerts.extend(critical_alerts(data, &waqts));
+    alerts.extend(sound_alerts(data, &waqts));
+    alerts.extend(announcement_alerts(data, &waqts));
+    alerts.extend(alarm::alerts(data));
+    alerts.extend(hooks::alerts(data, &waqts));
+    alerts.extend(popup_alerts(data, &waqts));
+    alerts.extend(events::alerts(data));
+    alerts.extend(fasting::alerts(data));
+    alerts
+}
+
+fn format_time(time: DateTime<Utc>) -> String {
+    time.with_timezone(&Local).format("%I:%M %p").to_string()
+}
+
+fn critical_alerts(data: &AppState, waqts: &[Waqt]) -> Vec<Alert> {
+    waqts
+        .iter()
+        .copied()
+        .filter(|waqt| {
+            *data.config.notifications.critical.get(waqt.prayer) && !data.is_waqt_prayed(waqt)
+        })
+        .map(|waqt| {
+            let at = waqt.end - Duration::minutes(data.config.critical_at as _);
+            Alert {
+                key: waqt_key("critical", &waqt),
+                kind: AlertKind::Critical(waqt.prayer),
+                at,
+                summary: data
+                    .remaining_of_waqt(waqt.prayer, waqt.end, at)
+                    .0
+                    .to_string(),
+                body: format!("{} ends at {}", waqt.label, format_time(waqt.end)),
+            }
+        })
+        .collect()
+}
+
+/// The waqt an alert raised at `at` about `prayer` is for: the first of the
+/// prayer not over by then.
+fn waqt_at(data: &AppState, prayer: Prayer, at: DateTime<Utc>) -> Option<Waqt> {
+    data.waqts_since_yesterday()
+        .into_iter()
+        .find(|waqt| waqt.prayer == prayer && waqt.end > at)
+}
+
+/// Keys alerts of a waqt by its local date rather than its exact time, so that
+/// a recalculation shifting the time does not raise it again.
+pub fn waqt_key(kind: &str, waqt: &Waqt) -> String {
+    format!(
+        "{}/{}/{}",
+        kind,
+        waqt.start.with_timezone(&Local).date().naive_local(),
+        waqt.label
+    )
+}
+
+fn before_start_alerts(data: &AppState, waqts: &[Waqt]) -> Vec<Alert> {
+    waqts
+        .iter()
+        .flat_map(|&waqt| {
+            data.config
+                .notifications
+                .before_start
+                .get(waqt.prayer)
+                .0
+                .iter()
+                .map(move |&minutes| {
+                    let at = waqt.start - Duration::minutes(minutes as _);
+                    Alert {
+                        key: format!("{}/{}", waqt_key("before", &waqt), minutes),
+                        kind: AlertKind::BeforeStart(waqt.prayer, minutes),
+                        at,
+                        summary: data
+                            .remaining_to_waqt(waqt.prayer, waqt.start, at)
+                            .0
+                            .to_string(),
+                        body: format!("{} at {}", waqt.label, format_time(waqt.start)),
+                    }
+                })
+        })
+        .collect()
+}
+
+fn waqt_start_alerts(data: &AppState, waqts: &[Waqt]) -> Vec<Alert> {
+    waqts
+        .iter()
+        .filter(|waqt| *data.config.notifications.at_start.get(waqt.prayer))
+        .map(|waqt| Alert {
+            key: waqt_key("start", waqt),
+            kind: AlertKind::WaqtStart(waqt.prayer),
+            at: waqt.start,
+            summary: match waqt.prayer {
+                Prayer::Sunrise if data.config.ishraq.is_none() => "The sun has risen".into(),
+                _ => format!("It is time for {}", waqt.label),
+            },
+            body: format!("Until {}", format_time(waqt.end)),
+        })
+        .collect()
+}
+
+fn sound_alerts(data: &AppState, waqts: &[Waqt]) -> Vec<Alert> {
+    waqts
+        .iter()
+        .filter_map(|waqt| {
+            let sound = *data.config.audio.at_start.get(waqt.prayer);
+            (sound != Sound::Off).then(|| Alert {
+                key: waqt_key("sound", waqt),
+                kind: AlertKind::Sound(waqt.prayer, sound),
+                at: waqt.start,
+                summary: waqt.label.into(),
+                body: String::new(),
+            })
+        })
+        .collect()
+}
+
+fn announcement_alerts(data: &AppState, waqts: &[Waqt]) -> Vec<Alert> {
+    waqts
+        .iter()
+        .enumerate()
+        .filter(|(_, waqt)| *data.config.audio.speak.get(waqt.prayer))
+        .map(|(index, waqt)| Alert {
+            key: waqt_key("announce", waqt),
+            // Qiyam of today is followed by Fajr of tomorrow.
+            kind: AlertKind::Announce(
+                waqt.prayer,
+                TimeLeft::between(waqt.end, waqt.start),
+                waqts.get(index + 1).map_or("Fajr", |next| next.label),
+            ),
+            at: waqt.start,
+            summary: waqt.label.into(),
+            body: data.remaining_in_waqt(waqt.end, waqt.start).0.to_string(),
+        })
+        .collect()
+}
+
+/// The spoken text of an announcement, localized by the `announce-waqt-start`
+/// message with the `prayer`, `time` and `next` arguments. The time left is
+/// told by `time-left`, as it is under the rows of the main window.
+fn announcement(alert: &Alert, time_left: TimeLeft, next: &'static str, env: &Env) -> String {
+    let prayer = alert.summary.clone();
+    let time = time_left.localized(env);
+    let mut text = LocalizedString::<()>::new("announce-waqt-start")
+        .with_placeholder(format!(
+            "It is now time for {}, {} until {}",
+            prayer, time_left, next
+        ))
+        .with_arg("prayer", move |_, _| prayer.clone().into())
+        .with_arg("time", move |_, _| time.clone().into())
+        .with_arg("next", move |_, _| next.into());
+    text.resolve(&(), env);
+    text.localized_str().to_string()
+}
+
+fn popup_alerts(data: &AppState, waqts: &[Waqt]) -> Vec<Alert> {
+    let popup = data.config.notifications.popup;
+    waqts
+        .iter()
+        .flat_map(|waqt| {
+            let at_start = popup.at_start.then(|| Alert {
+                key: waqt_key("popup", waqt),
+                kind: AlertKind::Popup(waqt.prayer),
+                at: waqt.start,
+                summary: String::new(),
+                body: String::new(),
+            });
+            let at_critical = (popup.at_critical && !data.is_waqt_prayed(waqt)).then(|| Alert {
+                key: waqt_key("popup/critical", waqt),
+                kind: AlertKind::Popup(waqt.prayer),
+                at: waqt.end - Duration::minutes(data.config.critical_at as _),
+                summary: String::new(),
+                body: String::new(),
+            });
+            at_start.into_iter().chain(at_critical)
+        })
+        .collect()
+}
+
+/// Alerts older than this when noticed, e.g. after the computer wakes from sleep,
+/// are dropped instead of being raised late.
+const STALE_AFTER_MINUTES: i64 = 5;
+
+pub struct AlertController {
+    timer: TimerToken,
+    /// Polls for the end of playback while a sound is playing.
+    playback_timer: TimerToken,
+    /// Rings again whenever the chime ends, while the alarm is on.
+    alarm_timer: TimerToken,
+    /// Hides the main window brought up by a popup alert.
+    popup_timer: TimerToken,
+    last_check: Option<DateTime<Utc>>,
+    fired: HashMap<String, DateTime<Utc>>,
+    snoozed: Vec<Alert>,
+    /// Media players paused for an adhan or announcement, to resume after.
+    paused: Option<media::Paused>,
+}
+
+impl AlertController {
+    pub fn new() -> AlertController {
+        AlertController {
+            timer: TimerToken::INVALID,
+            playback_timer: TimerToken::INVALID,
+            alarm_timer: TimerToken::INVALID,
+            popup_timer: TimerToken::INVALID,
+            last_check: None,
+            fired: HashMap::new(),
+            snoozed: vec![],
+            paused: None,
+        }
+    }
+
+    /// The alerts that became due since the last check.
+    fn check(&mut self, data: &AppState, now: DateTime<Utc>) -> Vec<Alert> {
+        let since = self
+            .last_check
+            .unwrap_or(now)
+            .max(now - Duration::minutes(STALE_AFTER_MINUTES));
+
+        // Snoozed alerts of a waqt prayed since are dropped along with its
+        // critical alerts.
+        let snoozed = self.snoozed.iter().filter(|alert| {
+            alert.kind.prayer().map_or(true, |prayer| {
+                waqt_at(data, prayer, alert.at).map_or(true, |waqt| !data.is_waqt_prayed(&waqt))
+            })
+        });
+        let due = scheduled(data)
+            .into_iter()
+            .chain(snoozed.cloned())
+            .filter(|alert| {
+                alert.at > since && alert.at <= now && !self.fired.contains_key(&alert.key)
+            })
+            .collect::<Vec<_>>();
+        for alert in &due {
+            self.fired.insert(alert.key.clone(), alert.at);
+        }
+
+        self.fired.retain(|_, at| now - *at < Duration::days(2));
+        self.snoozed.retain(|alert| alert.at > now);
+        self.last_check = Some(now);
+        due
+    }
+
+    fn resume_media(&mut self, data: &AppState) {
+        if let Some(paused) = self.paused.take() {
+            if data.config.audio.resume_media {
+                media::resume(paused);
+            }
+        }
+    }
+
+    fn act(&mut self, data: &mut AppState, alert: Alert, action: Action, now: DateTime<Utc>) {
+        self.resume_media(data);
+        match action {
+            Action::Snooze(minutes) => {
+                // Snoozing runs up to the critical alert of the waqt at most,
+                // or to its end for the critical alert itself.
+                let until = alert
+                    .kind
+                    .prayer()
+                    .and_then(|prayer| waqt_at(data, prayer, alert.at))
+                    .map(|waqt| match alert.kind {
+                        AlertKind::Critical(_) => waqt.end,
+                        _ => waqt.end - Duration::minutes(data.config.critical_at as _),
+                    });
+                let at = match until {
+                    Some(until) => (now + Duration::minutes(minutes as _)).min(until),
+                    None => now + Duration::minutes(minutes as _),
+                };
+                if at > now {
+                    self.snoozed.push(Alert {
+                        key: format!("{}/snooze/{}", alert.key, at),
+                        at,
+                        ..alert
+                    });
+                }
+            }
+            Action::Prayed => {
+                if let Some(waqt) = alert
+                    .kind
+                    .prayer()
+                    .and_then(|prayer| waqt_at(data, prayer, alert.at))
+                {
+                    data.set_waqt_prayed(&waqt, true);
+                }
+            }
+        }
+    }
+
+    fn raise(&mut self, ctx: &mut EventCtx, data: &mut AppState, env: &Env, alert: Alert) {
+        tracing::debug!("Raising alert `{}`", alert.key);
+        let quiet = !matches!(alert.kind, AlertKind::Alarm | AlertKind::Hook(..)) && is_quiet(data);
+        // Only what is heard pauses media, as it is resumed once it is over.
+        let heard = matches!(alert.kind, AlertKind::Sound(..) | AlertKind::Announce(..));
+        if data.config.audio.pause_media && heard && !quiet {
+            let paused = media::pause_all();
+            match &mut self.paused {
+                Some(previously) => previously.extend(paused),
+                None => self.paused = Some(paused),
+            }
+        }
+        match alert.kind {
+            AlertKind::Sound(prayer, _) | AlertKind::Announce(prayer, ..) if quiet => {
+                let notified = *data.config.notifications.at_start.get(prayer);
+                let sounded = *data.config.audio.at_start.get(prayer) != Sound::Off;
+                // A single silent notification stands in for all that would be
+                // heard at the start.
+                if !notified && (matches!(alert.kind, AlertKind::Sound(..)) || !sounded) {
+                    let summary = format!("It is time for {}", alert.summary);
+                    self.notify(
+                        ctx,
+                        Alert {
+                            kind: AlertKind::WaqtStart(prayer),
+                            summary,
+                            ..alert
+                        },
+                        true,
+                    );
+                }
+            }
+            AlertKind::Sound(prayer, sound) => {
+                if audio::play(sound, prayer, &data.config.audio) {
+                    data.playing = true;
+                    self.playback_timer = ctx.request_timer(PLAYBACK_POLL);
+                }
+            }
+            AlertKind::Announce(_, time_left, next) => {
+                speech::speak(&announcement(&alert, time_left, next, env));
+                self.playback_timer = ctx.request_timer(PLAYBACK_POLL);
+            }
+            AlertKind::Alarm => {
+                if data.alarm.is_none() {
+                    data.alarm = Some(Alarm::new(Utc::now(), data.config.fajr_alarm.puzzle));
+                    data.alarm_snoozed_until = None;
+                    ctx.new_window(ui_alarm::window());
+                    self.alarm_timer = ctx.request_timer(PLAYBACK_POLL);
+                }
+            }
+            AlertKind::Hook(event, waqt) => hooks::run_for_waqt(data, event, waqt),
+            AlertKind::Popup(_) => {
+                // Never over a full-screen application, whatever the settings.
+                if !quiet && !presence::do_not_disturb() {
+                    ctx.submit_command(ui_main::selector::SHOW);
+                    if let Some(seconds) = data.config.notifications.popup.hide_after {
+                        self.popup_timer =
+                            ctx.request_timer(std::time::Duration::from_secs(seconds as _));
+                    }
+                }
+            }
+            _ => self.notify(ctx, alert, quiet),
+        }
+    }
+
+    fn notify(&self, ctx: &mut EventCtx, alert: Alert, silent: bool) {
+        let sink = ctx.get_external_handle();
+        let actions = alert.kind.actions();
+        notification::show_with_actions(
+            &alert.summary,
+            &alert.body,
+            &actions,
+            silent,
+            move |action| {
+                if let Err(e) =
+                    sink.submit_command(ALERT_ACTION, (alert.clone(), action), Target::Auto)
+                {
+                    tracing::error!("Failed to submit notification action: {:?}", e);
+                }
+            },
+        );
+    }
+}
+
+/// Whether alerts should make no sound, during quiet hours or the desktop's
+/// do-not-disturb. The Fajr alarm rings regardless.
+fn is_quiet(data: &AppState) -> bool {
+    let quiet = &data.config.quiet;
+    quiet.is_quiet_at(Local::now().naive_local())
+        || (quiet.follow_do_not_disturb && presence::do_not_disturb())
+}
+
+const PLAYBACK_POLL: std::time::Duration = std::time::Duration::from_secs(1);
+
+pub fn until_next_minute() -> std::time::Duration {
+    let now = Utc::now();
+    std::time::Duration::from_secs(60 - now.second() as u64)
+        - std::time::Duration::from_nanos(now.nanosecond() as _)
+}
+
+impl<W: Widget<AppState>> druid::widget::Controller<AppState, W> for AlertController {
+    fn event(
+        &mut self,
+        child: &mut W,
+        ctx: &mut EventCtx,
+        event: &Event,
+        data: &mut AppState,
+        env: &Env,
+    ) {
+        match event {
+            Event::Timer(token) if *token == self.timer => {
+                for alert in self.check(data, Utc::now()) {
+                    self.raise(ctx, data, env, alert);
+                }
+                self.timer = ctx.request_timer(until_next_minute());
+            }
+            Event::Timer(token) if *token == self.playback_timer => {
+                data.playing = data.playing && audio::is_playing();
+                if data.playing || speech::is_speaking() {
+                    self.playback_timer = ctx.request_timer(PLAYBACK_POLL);
+                } else {
+                    self.resume_media(data);
+                }
+            }
+            Event::Command(command) if command.is(ALERT_ACTION) => {
+                let (alert, action) = command.get_unchecked(ALERT_ACTION).clone();
+                self.act(data, alert, action, Utc::now());
+            }
+            Event::Timer(token) if *token == self.popup_timer => {
+                ctx.submit_command(ui_main::selector::HIDE);
+            }
+            Event::Timer(token) if *token == self.alarm_timer => {
+                if let Some(alarm) = &data.alarm {
+                    if !audio::is_playing() {
+                        audio::ring(alarm.volume(Utc::now()));
+                    }
+                    self.alarm_timer = ctx.request_timer(PLAYBACK_POLL);
+                }
+            }
+            _ => child.event(ctx, event, data, env),
+        }
+    }
+
+    fn lifecycle(
+        &mut self,
+        child: &mut W,
+        ctx: &mut LifeCycleCtx,
+        event: &LifeCycle,
+        data: &AppState,
+        env: &Env,
+    ) {
+        if let LifeCycle::WidgetAdded = event {
+            self.timer = ctx.request_timer(until_next_minute());
+        }
+        child.lifecycle(ctx, event, data, env)
+    }
+}
@@ -1,9 +1,19 @@
+use std::{cell::RefCell, fmt, sync::Arc};
+
 use adhaan::*;
-use chrono::{DateTime, Duration, Utc};
-use druid::Lens;
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+use druid::{Env, Lens, LocalizedString};
 
 use crate::{
+    alarm::Alarm,
     config::{AsrConfig, Config},
+    events::{self, HijriEvent},
+    export,
+    hijri::HijriDate,
+    moon::MoonInfo,
+    qibla::QiblaInfo,
+    timeline::Timeline,
+    timetable::{self, Row},
     utils::{lens_map_get, DataWrapper},
 };
 
@@ -11,11 +21,374 @@ use crate::{
 pub struct AppState {
     pub config: Config,
 
+    /// Local date `prayers` are calculated for.
+    #[data(same_fn = "PartialEq::eq")]
+    pub date: NaiveDate,
+
     #[data(same_fn = "PartialEq::eq")]
     pub prayers: PrayerTimes,
+
+    pub user_events: Arc<Vec<HijriEvent>>,
+
+    /// Waqts marked as prayed, by the date of `prayers` they belong to.
+    #[data(same_fn = "PartialEq::eq")]
+    pub prayed: Vec<(NaiveDate, Prayer)>,
+
+    /// Whether an adhan or chime is playing, to offer stopping it.
+    pub playing: bool,
+
+    /// The moon as of the last minute tick.
+    pub moon: DataWrapper<MoonInfo>,
+
+    /// The Qibla of the shown day, kept by `refresh_qibla`.
+    pub qibla: DataWrapper<QiblaInfo>,
+    /// The day and coordinates `qibla` is calculated for.
+    #[data(ignore)]
+    qibla_of: (NaiveDate, Coordinates),
+
+    /// The timeline of `date`, kept by `refresh_shown`.
+    pub timeline: DataWrapper<Timeline>,
+    /// The day and configuration `timeline` is laid out for.
+    #[data(ignore)]
+    timeline_of: Option<(NaiveDate, Config)>,
+
+    /// The Fajr alarm while it rings.
+    pub alarm: Option<Alarm>,
+    /// When the snoozed Fajr alarm rings again.
+    #[data(same_fn = "PartialEq::eq")]
+    pub alarm_snoozed_until: Option<DateTime<Utc>>,
+
+    /// The day shown in the main window, when not today.
+    #[data(same_fn = "PartialEq::eq")]
+    pub viewing: Option<NaiveDate>,
+    /// The state as of `viewing`, kept by `view` and `refresh_shown` for the
+    /// main window to show.
+    viewed: Option<Arc<AppState>>,
+    /// A day of the month shown in the date picker.
+    #[data(same_fn = "PartialEq::eq")]
+    pub picker_month: NaiveDate,
+
+    /// A day of the month shown in the timetable window.
+    #[data(same_fn = "PartialEq::eq")]
+    pub timetable_month: NaiveDate,
+    /// The days of `timetable_month`, kept by `refresh_timetable`.
+    #[data(same_fn = "Arc::ptr_eq")]
+    pub timetable: Arc<Vec<Row>>,
+    /// The first day of the month, today and the configuration `timetable` is
+    /// calculated for.
+    #[data(ignore)]
+    timetable_of: Option<(NaiveDate, NaiveDate, Config)>,
+    /// The days to export from the timetable window.
+    pub export_span: export::Span,
+    pub export_from: String,
+    pub export_to: String,
+}
+
+#[derive(Default)]
+struct ShownLens {
+    /// A copy of the viewed state for events to take mutably, made again only
+    /// when the viewed state changes.
+    scratch: RefCell<Option<AppState>>,
+}
+
+impl Lens<AppState, AppState> for ShownLens {
+    fn with<V, F: FnOnce(&AppState) -> V>(&self, data: &AppState, f: F) -> V {
+        match &data.viewed {
+            Some(viewed) => f(viewed),
+            None => f(data),
+        }
+    }
+
+    fn with_mut<V, F: FnOnce(&mut AppState) -> V>(&self, data: &mut AppState, f: F) -> V {
+        let viewed = match &data.viewed {
+            Some(viewed) => viewed,
+            None => return f(data),
+        };
+
+        let mut scratch = self.scratch.borrow_mut();
+        if !matches!(&*scratch, Some(scratch) if scratch.same(viewed)) {
+            *scratch = Some(AppState::clone(viewed));
+        }
+        let scratch = scratch.as_mut().unwrap();
+        let value = f(scratch);
+        // Other days are only looked at.
+        debug_assert!(scratch.same(viewed), "the viewed day was changed");
+        value
+    }
+}
+
+/// A span of time for a prayer, as shown in the main window.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Waqt {
+    pub prayer: Prayer,
+    /// The date of `prayers` the waqt belongs to, which its prayed mark is
+    /// recorded against.
+    pub date: NaiveDate,
+    pub label: &'static str,
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
 }
 
 impl AppState {
+    pub fn new(config: Config, date: NaiveDate, user_events: Arc<Vec<HijriEvent>>) -> AppState {
+        AppState {
+            prayers: Self::calculate_prayers(&config, date),
+            moon: DataWrapper(MoonInfo::at(Utc::now(), config.coordinates)),
+            qibla: DataWrapper(QiblaInfo::at(date, config.coordinates)),
+            qibla_of: (date, config.coordinates),
+            timeline: DataWrapper(Timeline::default()),
+            timeline_of: None,
+            config,
+            date,
+            user_events,
+            prayed: vec![],
+            playing: false,
+            alarm: None,
+            alarm_snoozed_until: None,
+            viewing: None,
+            viewed: None,
+            picker_month: date,
+            timetable_month: date,
+            timetable: Arc::new(vec![]),
+            timetable_of: None,
+            export_span: export::Span::Month,
+            export_from: String::new(),
+            export_to: String::new(),
+        }
+    }
+
+    fn calculate_prayers(config: &Config, date: NaiveDate) -> PrayerTimes {
+        PrayerTimes::calculate(date, config.coordinates, config.method.get_parameters()).unwrap()
+    }
+
+    /// Recalculates the prayer times for `date`.
+    pub fn recalculate(&mut self, date: NaiveDate) {
+        self.prayers = Self::calculate_prayers(&self.config, date);
+        self.date = date;
+        self.prayed
+            .retain(|(prayed_on, _)| *prayed_on >= date.pred());
+    }
+
+    /// Recalculates the moon, once a minute.
+    pub fn refresh_moon(&mut self) {
+        self.moon = DataWrapper(MoonInfo::at(Utc::now(), self.config.coordinates));
+    }
+
+    /// Recalculates the Qibla for the shown day, unless it is of the same day
+    /// and coordinates already.
+    pub fn refresh_qibla(&mut self) {
+        let of = (self.shown_date(), self.config.coordinates);
+        if self.qibla_of != of {
+            self.qibla = DataWrapper(QiblaInfo::at(of.0, of.1));
+            self.qibla_of = of;
+        }
+    }
+
+    /// Lays out the timeline of today and of the viewed day again, and keeps
+    /// the state of the viewed day up with today's.
+    pub fn refresh_shown(&mut self) {
+        self.refresh_timeline();
+        self.refresh_viewed();
+    }
+
+    /// Lays out the timeline again, unless it is of the same day and
+    /// configuration already.
+    fn refresh_timeline(&mut self) {
+        let of = (self.date, self.config.clone());
+        if self.timeline_of.as_ref() != Some(&of) {
+            self.timeline = DataWrapper(Timeline::of(self));
+            self.timeline_of = Some(of);
+        }
+    }
+
+    /// Makes the state of `viewing` from today's, calculating its prayer times
+    /// and timeline again only when the day or configuration changes.
+    fn refresh_viewed(&mut self) {
+        let previous = self.viewed.take();
+        self.viewed = self.viewing.map(|date| {
+            let mut viewed = AppState {
+                date,
+                ..self.clone()
+            };
+            match previous.as_deref() {
+                Some(previous) if previous.date == date && previous.config == self.config => {
+                    viewed.prayers = previous.prayers.clone();
+                    viewed.timeline = previous.timeline.clone();
+                    viewed.timeline_of = previous.timeline_of.clone();
+                }
+                _ => viewed.prayers = Self::calculate_prayers(&self.config, date),
+            }
+            viewed.refresh_timeline();
+            Arc::new(viewed)
+        });
+    }
+
+    /// Recalculates the days of the timetable, unless they are of the same month,
+    /// today and configuration already.
+    pub fn refresh_timetable(&mut self) {
+        let (first, _) = timetable::month_bounds(self.timetable_month);
+        if matches!(&self.timetable_of, Some((month, today, config))
+            if *month == first && *today == self.date && *config == self.config)
+        {
+            return;
+        }
+
+        let rows = timetable::month_of(self, first)
+            .into_iter()
+            .map(|day| {
+                let is_today = day.date == self.date;
+                DataWrapper((day, is_today, self.config.asr))
+            })
+            .collect();
+        self.timetable = Arc::new(rows);
+        self.timetable_of = Some((first, self.date, self.config.clone()));
+    }
+
+    /// The state as it would be on `date`, for showing other days than today.
+    pub fn on_date(&self, date: NaiveDate) -> AppState {
+        let mut day = self.clone();
+        day.recalculate(date);
+        day
+    }
+
+    /// The day shown in the main window.
+    pub fn shown_date(&self) -> NaiveDate {
+        self.viewing.unwrap_or(self.date)
+    }
+
+    /// Shows `date` in the main window.
+    pub fn view(&mut self, date: NaiveDate) {
+        self.viewing = (date != self.date).then(|| date);
+        self.refresh_viewed();
+    }
+
+    /// The state of the day shown in the main window: the state itself for
+    /// today, and the state kept by `view` for other days. Other days are only
+    /// looked at.
+    pub fn lens_shown() -> impl Lens<Self, AppState> {
+        ShownLens::default()
+    }
+
+    /// The prayer a waqt is recorded against, whichever of its rows or aliases
+    /// names it.
+    fn canonical(&self, prayer: Prayer) -> Prayer {
+        use Prayer::*;
+
+        match prayer {
+            AsrAwwal | AsrThaani if self.config.travel.enabled => Dhuhr,
+            AsrThaani => AsrAwwal,
+            Isha | Yesterday if self.config.travel.enabled => Maghrib,
+            Yesterday => Isha,
+            QiyamYesterday => Qiyam,
+            Tomorrow => Fajr,
+            prayer => prayer,
+        }
+    }
+
+    /// The date a row of `prayer` belongs to, the night before for the rows of
+    /// yesterday.
+    fn date_of(&self, prayer: Prayer) -> NaiveDate {
+        match prayer {
+            Prayer::Yesterday | Prayer::QiyamYesterday => self.date.pred(),
+            Prayer::Tomorrow => self.date.succ(),
+            _ => self.date,
+        }
+    }
+
+    pub fn is_prayed(&self, prayer: Prayer) -> bool {
+        self.is_prayed_on(self.date_of(prayer), prayer)
+    }
+
+    pub fn set_prayed(&mut self, prayer: Prayer, prayed: bool) {
+        self.set_prayed_on(self.date_of(prayer), prayer, prayed)
+    }
+
+    pub fn is_waqt_prayed(&self, waqt: &Waqt) -> bool {
+        self.is_prayed_on(waqt.date, waqt.prayer)
+    }
+
+    pub fn set_waqt_prayed(&mut self, waqt: &Waqt, prayed: bool) {
+        self.set_prayed_on(waqt.date, waqt.prayer, prayed)
+    }
+
+    fn is_prayed_on(&self, date: NaiveDate, prayer: Prayer) -> bool {
+        self.prayed.contains(&(date, self.canonical(prayer)))
+    }
+
+    /// Marks the waqt of `prayer` on `date`, saving the marks to survive a
+    /// restart.
+    fn set_prayed_on(&mut self, date: NaiveDate, prayer: Prayer, prayed: bool) {
+        let entry = (date, self.canonical(prayer));
+        self.prayed.retain(|e| *e != entry);
+        if prayed {
+            self.prayed.push(entry);
+        }
+        if let Err(e) = crate::prayed::save(&self.prayed) {
+            tracing::error!("Failed to save prayed marks: {:?}", e);
+        }
+        self.refresh_viewed();
+    }
+
+    /// The waqts of the day in order, following the Asr, Ishraq and travel
+    /// configurations like the rows of the main window.
+    pub fn waqts(&self) -> Vec<Waqt> {
+        use Prayer::*;
+
+        let waqt = |prayer, start, end| Waqt {
+            prayer,
+            date: self.date,
+            label: self.label_of(prayer),
+            start,
+            end,
+        };
+        let time_of = |prayer| self.prayers.time_of(prayer).unwrap();
+
+        let mut waqts = vec![waqt(Fajr, self.adjusted_time_of(Fajr), time_of(Sunrise))];
+
+        waqts.push(match self.config.ishraq {
+            Some((after_sunrise, zawal)) => waqt(
+                Sunrise,
+                self.adjusted_time_of(Sunrise) + Duration::minutes(after_sunrise as _),
+                time_of(Dhuhr) - Duration::minutes(zawal as _),
+            ),
+            None => waqt(Sunrise, self.adjusted_time_of(Sunrise), time_of(Dhuhr)),
+        });
+
+        if self.config.travel.enabled {
+            for prayer in [Dhuhr, Maghrib] {
+                let (start, end) = self.combined_waqt_of(prayer).unwrap();
+                waqts.push(waqt(prayer, start, end));
+            }
+        } else {
+            let (asr, dhuhr_ends_with) = match self.config.asr {
+                AsrConfig::DhuhrEndsAtAsrAwwal => (AsrAwwal, AsrAwwal),
+                AsrConfig::DhuhrEndsAtAsrThaaniButAsrStartsAtAsrAwwal => (AsrAwwal, AsrThaani),
+                AsrConfig::AsrStartsAtAsrThaani(_) => (AsrThaani, AsrThaani),
+            };
+            waqts.push(waqt(
+                Dhuhr,
+                self.adjusted_time_of(Dhuhr),
+                time_of(dhuhr_ends_with),
+            ));
+            waqts.push(waqt(asr, self.adjusted_time_of(asr), time_of(Maghrib)));
+            waqts.push(waqt(Maghrib, self.adjusted_time_of(Maghrib), time_of(Isha)));
+            waqts.push(waqt(Isha, self.adjusted_time_of(Isha), time_of(Qiyam)));
+        }
+
+        waqts.push(waqt(Qiyam, self.adjusted_time_of(Qiyam), time_of(Tomorrow)));
+
+        waqts
+    }
+
+    /// The waqts of yesterday and today in order. Alerts are scheduled from
+    /// these, as the waqts of a night run past midnight into the next date of
+    /// `prayers`.
+    pub fn waqts_since_yesterday(&self) -> Vec<Waqt> {
+        let mut waqts = self.on_date(self.date.pred()).waqts();
+        waqts.extend(self.waqts());
+        waqts
+    }
+
     pub fn prayer_next(&self, prayer: Prayer) -> Prayer {
         use Prayer::*;
 
@@ -51,6 +424,8 @@ impl AppState {
     pub fn label_of(&self, prayer: Prayer) -> &'static str {
         use Prayer::*;
         match prayer {
+            Dhuhr if self.config.travel.enabled => "Dhuhr & Asr",
+            Maghrib | Yesterday if self.config.travel.enabled => "Maghrib & Isha",
             Yesterday => "Isha",
             QiyamYesterday => "Qiyam",
             Fajr => "Fajr",
@@ -77,23 +452,69 @@ impl AppState {
         self.prayers.time_of(prayer).unwrap() + self.adjustment_for(prayer)
     }
 
+    /// The Hijri date at `now`, which changes at Maghrib rather than at midnight.
+    pub fn hijri_date(&self, now: DateTime<Utc>) -> HijriDate {
+        let mut date = now.with_timezone(&chrono::Local).date().naive_local();
+        if now >= self.adjusted_time_of(Prayer::Maghrib) {
+            date = date.succ();
+        }
+        self.hijri_date_on(date)
+    }
+
+    /// The Hijri date during the daytime of the civil `date`.
+    pub fn hijri_date_on(&self, date: NaiveDate) -> HijriDate {
+        HijriDate::from_gregorian(
+            date + Duration::days(self.config.hijri.offset as _),
+            self.config.hijri.calendar,
+        )
+    }
+
+    pub fn lens_hijri_date() -> impl Lens<Self, String> {
+        lens_map_get(|data: &Self| data.hijri_date(Utc::now()).to_string())
+    }
+
+    pub fn events(&self) -> Vec<HijriEvent> {
+        let mut events = events::builtin_events(&self.config);
+        events.extend(self.user_events.iter().cloned());
+        events
+    }
+
+    pub fn lens_upcoming_event() -> impl Lens<Self, Option<String>> {
+        lens_map_get(|data: &Self| {
+            let now = Utc::now();
+            let today = now.with_timezone(&chrono::Local).date().naive_local();
+            events::upcoming(&data.events(), data.hijri_date(now), today, &data.config).map(
+                |occurrence| match (occurrence.date - today).num_days() {
+                    0 => format!("{} today", occurrence.event.name),
+                    1 if occurrence.event.night => format!("{} tonight", occurrence.event.name),
+                    1 => format!("{} tomorrow", occurrence.event.name),
+                    n => format!("{} in {} days", occurrence.event.name, n),
+                },
+            )
+        })
+    }
+
+    pub fn lens_moon_phase() -> impl Lens<Self, (f64, bool)> {
+        lens_map_get(|data: &Self| (data.moon.0.illumination, data.moon.0.waxing))
+    }
+
+    pub fn lens_moon() -> impl Lens<Self, DataWrapper<MoonInfo>> {
+        lens_map_get(|data: &Self| data.moon.clone())
+    }
+
+    pub fn lens_qibla() -> impl Lens<Self, DataWrapper<QiblaInfo>> {
+        lens_map_get(|data: &Self| data.qibla.clone())
+    }
+
+    pub fn lens_timeline() -> impl Lens<Self, DataWrapper<Timeline>> {
+        lens_map_get(|data: &Self| data.timeline.clone())
+    }
+
     pub fn lens_prayer(prayer: Prayer) -> impl Lens<Self, PrayerLensed> {
         lens_map_get(move |data: &Self| {
-            let starts_at = data.adjusted_time_of(prayer);
-            let now = Utc::now();
-            let elapsed_critical = (prayer == data.prayers.prayer_at(now)).then(|| {
-                let end = data.prayers.time_of(data.prayer_next(prayer)).unwrap();
-                let remaining = end - now;
-                let remaining_fraction =
-                    remaining.num_seconds() as f64 / (end - starts_at).num_seconds() as f64;
-                (
-                    1.0 - remaining_fraction,
-                    remaining.num_minutes() < data.config.critical_at as _,
-                )
-            });
             (
-                DataWrapper((starts_at, data.label_of(prayer))),
-                elapsed_critical,
+                DataWrapper((data.adjusted_time_of(prayer), data.label_of(prayer))),
+                data.progress_of(prayer),
             )
         })
     }
@@ -102,25 +523,13 @@ impl AppState {
         lens_map_get(|data: &Self| {
             let starts_at = data.adjusted_time_of(Prayer::Sunrise);
 
-            if let Some((after_sunrise, zawal)) = data.config.ishraq {
-                let starts_at = starts_at + chrono::Duration::minutes(after_sunrise as _);
-                let ends_at = data.prayers.time_of(Prayer::Dhuhr).unwrap()
-                    - chrono::Duration::minutes(zawal as _);
-                let now = Utc::now();
-
-                let elapsed_critical = (starts_at..=ends_at).contains(&now).then(|| {
-                    let remaining = ends_at - now;
-                    let remaining_fraction =
-                        remaining.num_seconds() as f64 / (ends_at - starts_at).num_seconds() as f64;
-                    (
-                        1.0 - remaining_fraction,
-                        remaining.num_minutes() < data.config.critical_at as _,
-                    )
-                });
-
+            if let Some((after_sunrise, _)) = data.config.ishraq {
                 return (
-                    DataWrapper((starts_at, data.label_of(Prayer::Sunrise))),
-                    elapsed_critical,
+                    DataWrapper((
+                        starts_at + chrono::Duration::minutes(after_sunrise as _),
+                        data.label_of(Prayer::Sunrise),
+                    )),
+                    data.progress_of(Prayer::Sunrise),
                 );
             }
 
@@ -133,30 +542,12 @@ impl AppState {
 
     pub fn lens_dhuhr() -> impl Lens<Self, PrayerLensed> {
         lens_map_get(|data: &Self| {
-            let start = data.adjusted_time_of(Prayer::Dhuhr);
-            let end = data
-                .prayers
-                .time_of(if data.config.asr == AsrConfig::DhuhrEndsAtAsrAwwal {
-                    Prayer::AsrAwwal
-                } else {
-                    Prayer::AsrThaani
-                })
-                .unwrap();
-            let now = Utc::now();
-
-            let elapsed_critical = (start..=end).contains(&now).then(|| {
-                let remaining = end - now;
-                let remaining_fraction =
-                    remaining.num_seconds() as f64 / (end - start).num_seconds() as f64;
-                (
-                    1.0 - remaining_fraction,
-                    remaining.num_minutes() < data.config.critical_at as _,
-                )
-            });
-
             (
-                DataWrapper((start, data.label_of(Prayer::Dhuhr))),
-                elapsed_critical,
+                DataWrapper((
+                    data.adjusted_time_of(Prayer::Dhuhr),
+                    data.label_of(Prayer::Dhuhr),
+                )),
+                data.progress_of(Prayer::Dhuhr),
             )
         })
     }
@@ -166,153 +557,291 @@ impl AppState {
             let start_1 = data.adjusted_time_of(Prayer::AsrAwwal);
             let start_2 = data.adjusted_time_of(Prayer::AsrThaani);
 
-            let start = if let AsrConfig::AsrStartsAtAsrThaani(_) = data.config.asr {
-                start_2
-            } else {
-                start_1
-            };
-
             let start_2 = match data.config.asr {
                 AsrConfig::DhuhrEndsAtAsrAwwal | AsrConfig::AsrStartsAtAsrThaani(false) => None,
                 _ => Some((start_2, data.label_of(Prayer::AsrThaani))),
             };
 
-            let end = data.prayers.time_of(Prayer::Maghrib).unwrap();
-            let now = Utc::now();
-            let elapsed_critical = (start..=end).contains(&now).then(|| {
-                let remaining = end - now;
-                let remaining_fraction =
-                    remaining.num_seconds() as f64 / (end - start).num_seconds() as f64;
-                (
-                    1.0 - remaining_fraction,
-                    remaining.num_minutes() < data.config.critical_at as _,
-                )
-            });
             (
                 DataWrapper(((start_1, data.label_of(Prayer::AsrAwwal)), start_2)),
-                elapsed_critical,
+                data.progress_of(Prayer::AsrAwwal),
             )
         })
     }
 
-    pub fn lens_remaining() -> impl Lens<Self, (String, bool)> {
-        lens_map_get(|data: &Self| {
-            let now = Utc::now();
-            let prayer_now = data.prayers.prayer_at(now);
+    /// Start and end of the combined waqt `prayer` belongs to while travelling.
+    pub fn combined_waqt_of(&self, prayer: Prayer) -> Option<(DateTime<Utc>, DateTime<Utc>)> {
+        use Prayer::*;
 
-            if prayer_now == Prayer::Yesterday {
-                return data.make_str_remaining_in_waqt_labeled(
-                    prayer_now,
-                    data.prayers.time_of(Prayer::QiyamYesterday).unwrap(),
-                    now,
-                );
-            }
+        match prayer {
+            Dhuhr | AsrAwwal | AsrThaani => Some((
+                self.adjusted_time_of(Dhuhr),
+                self.prayers.time_of(Maghrib).unwrap(),
+            )),
+            Maghrib | Isha => Some((
+                self.adjusted_time_of(Maghrib),
+                self.prayers.time_of(Qiyam).unwrap(),
+            )),
+            _ => None,
+        }
+    }
+
+    pub fn lens_combined(prayer: Prayer) -> impl Lens<Self, PrayerLensed> {
+        lens_map_get(move |data: &Self| {
+            let (start, _) = data.combined_waqt_of(prayer).unwrap();
+            (
+                DataWrapper((start, data.label_of(prayer))),
+                data.progress_of(prayer),
+            )
+        })
+    }
 
-            let starts_at = data.adjusted_time_of(prayer_now);
-
-            if prayer_now == Prayer::Sunrise {
-                return if let Some((after_sunrise, zawal)) = data.config.ishraq {
-                    let starts_at = starts_at + chrono::Duration::minutes(after_sunrise as _);
-                    let dhuhr_starts_at = data.adjusted_time_of(Prayer::Dhuhr);
-                    let ends_at = data.prayers.time_of(Prayer::Dhuhr).unwrap()
-                        - chrono::Duration::minutes(zawal as _);
-
-                    if now < starts_at {
-                        data.make_str_remaining_to_waqt(Prayer::Sunrise, starts_at, now)
-                    } else if now > ends_at {
-                        data.make_str_remaining_to_waqt(Prayer::Dhuhr, dhuhr_starts_at, now)
-                    } else {
-                        data.make_str_remaining_in_waqt(ends_at, now)
-                    }
+    /// Progress of the waqt `prayer` names among `waqts`, the spans the
+    /// timeline is laid out from.
+    fn progress_of(&self, prayer: Prayer) -> Option<(f64, bool)> {
+        let prayer = self.canonical(prayer);
+        self.waqts()
+            .into_iter()
+            .find(|waqt| self.canonical(waqt.prayer) == prayer)
+            .and_then(|waqt| self.progress_between(waqt.start, waqt.end, Utc::now()))
+    }
+
+    /// Elapsed fraction of and whether critical is the waqt from `start` to
+    /// `end`, if `now` falls in it and today is shown.
+    fn progress_between(
+        &self,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+        now: DateTime<Utc>,
+    ) -> Option<(f64, bool)> {
+        (self.viewing.is_none() && (start..=end).contains(&now)).then(|| {
+            let remaining = end - now;
+            let remaining_fraction =
+                remaining.num_seconds() as f64 / (end - start).num_seconds() as f64;
+            (
+                1.0 - remaining_fraction,
+                remaining.num_minutes() < self.config.critical_at as _,
+            )
+        })
+    }
+
+    pub fn lens_remaining() -> impl Lens<Self, (DataWrapper<Remaining>, bool)> {
+        lens_map_get(|data: &Self| {
+            let (remaining, critical) = data.remaining_now();
+            (DataWrapper(remaining), critical)
+        })
+    }
+
+    /// The time left under the rows of the main window, and whether it is
+    /// critical.
+    fn remaining_now(&self) -> (Remaining, bool) {
+        let now = Utc::now();
+        let prayer_now = self.prayers.prayer_at(now);
+
+        if self.config.travel.enabled {
+            if let Some((starts_at, ends_at)) = self.combined_waqt_of(prayer_now) {
+                let first = match prayer_now {
+                    Prayer::Maghrib | Prayer::Isha => Prayer::Maghrib,
+                    _ => Prayer::Dhuhr,
+                };
+                return if now < starts_at {
+                    self.remaining_to_waqt(first, starts_at, now)
                 } else {
-                    data.make_str_remaining_to_waqt(
-                        Prayer::Dhuhr,
-                        data.prayers.time_of(Prayer::Dhuhr).unwrap(),
-                        now,
-                    )
+                    self.remaining_in_waqt(ends_at, now)
                 };
             }
+        }
 
-            let ends_with_prayer = match prayer_now {
-                Prayer::QiyamYesterday
-                | Prayer::Fajr
-                | Prayer::Maghrib
-                | Prayer::Isha
-                | Prayer::Qiyam => data.prayer_next(prayer_now),
-                Prayer::Dhuhr if data.config.asr == AsrConfig::DhuhrEndsAtAsrAwwal => {
-                    Prayer::AsrAwwal
-                }
-                Prayer::Dhuhr => Prayer::AsrThaani,
-                Prayer::AsrAwwal
-                    if data.config.asr == AsrConfig::DhuhrEndsAtAsrThaaniButAsrStartsAtAsrAwwal =>
-                {
-                    return data.make_str_remaining_in_waqt_labeled(
-                        Prayer::Dhuhr,
-                        data.prayers.time_of(Prayer::AsrThaani).unwrap(),
-                        now,
-                    );
-                }
-                Prayer::AsrAwwal | Prayer::AsrThaani => Prayer::Maghrib,
-                Prayer::Yesterday | Prayer::Sunrise | Prayer::Tomorrow => unreachable!(),
-            };
+        if prayer_now == Prayer::Yesterday {
+            return self.remaining_of_waqt(
+                prayer_now,
+                self.prayers.time_of(Prayer::QiyamYesterday).unwrap(),
+                now,
+            );
+        }
+
+        let starts_at = self.adjusted_time_of(prayer_now);
+
+        if prayer_now == Prayer::Sunrise {
+            return if let Some((after_sunrise, zawal)) = self.config.ishraq {
+                let starts_at = starts_at + chrono::Duration::minutes(after_sunrise as _);
+                let dhuhr_starts_at = self.adjusted_time_of(Prayer::Dhuhr);
+                let ends_at = self.prayers.time_of(Prayer::Dhuhr).unwrap()
+                    - chrono::Duration::minutes(zawal as _);
 
-            if now < starts_at {
-                data.make_str_remaining_to_waqt(prayer_now, starts_at, now)
+                if now < starts_at {
+                    self.remaining_to_waqt(Prayer::Sunrise, starts_at, now)
+                } else if now > ends_at {
+                    self.remaining_to_waqt(Prayer::Dhuhr, dhuhr_starts_at, now)
+                } else {
+                    self.remaining_in_waqt(ends_at, now)
+                }
             } else {
-                data.make_str_remaining_in_waqt(
-                    data.prayers.time_of(ends_with_prayer).unwrap(),
+                self.remaining_to_waqt(
+                    Prayer::Dhuhr,
+                    self.prayers.time_of(Prayer::Dhuhr).unwrap(),
                     now,
                 )
+            };
+        }
+
+        let ends_with_prayer = match prayer_now {
+            Prayer::QiyamYesterday
+            | Prayer::Fajr
+            | Prayer::Maghrib
+            | Prayer::Isha
+            | Prayer::Qiyam => self.prayer_next(prayer_now),
+            Prayer::Dhuhr if self.config.asr == AsrConfig::DhuhrEndsAtAsrAwwal => Prayer::AsrAwwal,
+            Prayer::Dhuhr => Prayer::AsrThaani,
+            Prayer::AsrAwwal
+                if self.config.asr == AsrConfig::DhuhrEndsAtAsrThaaniButAsrStartsAtAsrAwwal =>
+            {
+                return self.remaining_of_waqt(
+                    Prayer::Dhuhr,
+                    self.prayers.time_of(Prayer::AsrThaani).unwrap(),
+                    now,
+                );
             }
-        })
+            Prayer::AsrAwwal | Prayer::AsrThaani => Prayer::Maghrib,
+            Prayer::Yesterday | Prayer::Sunrise | Prayer::Tomorrow => unreachable!(),
+        };
+
+        if now < starts_at {
+            self.remaining_to_waqt(prayer_now, starts_at, now)
+        } else {
+            self.remaining_in_waqt(self.prayers.time_of(ends_with_prayer).unwrap(), now)
+        }
     }
 
-    fn make_str_remaining_in_waqt(
+    pub fn remaining_in_waqt(
         &self,
         ends_at: DateTime<Utc>,
         now: DateTime<Utc>,
-    ) -> (String, bool) {
-        let time_remaining = ends_at - now;
-        let mins_remaining = time_remaining.num_minutes() % 60;
-        let plural_mins = if mins_remaining == 0 || mins_remaining == 1 {
-            ""
-        } else {
-            "s"
-        };
+    ) -> (Remaining, bool) {
         (
-            match time_remaining.num_hours() {
-                0 => format!("{} minute{} remaining", mins_remaining, plural_mins),
-                1 => format!(
-                    "1 hour and {} minute{} remaining",
-                    mins_remaining, plural_mins
-                ),
-                n => format!(
-                    "{} hours and {} minute{} remaining",
-                    n, mins_remaining, plural_mins
-                ),
-            },
-            time_remaining.num_minutes() < self.config.critical_at as _,
+            Remaining::InWaqt(TimeLeft::between(ends_at, now)),
+            (ends_at - now).num_minutes() < self.config.critical_at as _,
         )
     }
 
-    fn make_str_remaining_in_waqt_labeled(
+    pub fn remaining_of_waqt(
         &self,
         prayer: Prayer,
         ends_at: DateTime<Utc>,
         now: DateTime<Utc>,
-    ) -> (String, bool) {
-        let (text, critical) = self.make_str_remaining_in_waqt(ends_at, now);
-        (format!("{} of {}", text, self.label_of(prayer)), critical)
+    ) -> (Remaining, bool) {
+        let (remaining, critical) = self.remaining_in_waqt(ends_at, now);
+        (
+            Remaining::OfWaqt(remaining.time_left(), self.label_of(prayer)),
+            critical,
+        )
     }
 
-    fn make_str_remaining_to_waqt(
+    pub fn remaining_to_waqt(
         &self,
         prayer: Prayer,
         starts_at: DateTime<Utc>,
         now: DateTime<Utc>,
-    ) -> (String, bool) {
-        let (text, critical) = self.make_str_remaining_in_waqt(starts_at, now);
-        (format!("{} to {}", text, self.label_of(prayer)), critical)
+    ) -> (Remaining, bool) {
+        let (remaining, critical) = self.remaining_in_waqt(starts_at, now);
+        (
+            Remaining::ToWaqt(remaining.time_left(), self.label_of(prayer)),
+            critical,
+        )
+    }
+}
+
+/// Hours and minutes left of a span of time, as in "2 hours 10 minutes".
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TimeLeft {
+    pub hours: i64,
+    pub minutes: i64,
+}
+
+impl TimeLeft {
+    pub fn between(until: DateTime<Utc>, now: DateTime<Utc>) -> TimeLeft {
+        let left = until - now;
+        TimeLeft {
+            hours: left.num_hours(),
+            minutes: left.num_minutes() % 60,
+        }
+    }
+
+    /// The `time-left` message, with the hours and minutes to select plurals
+    /// by.
+    pub fn localized(self, env: &Env) -> String {
+        let mut text = LocalizedString::<()>::new("time-left")
+            .with_placeholder(self.to_string())
+            .with_arg("hours", move |_, _| self.hours.into())
+            .with_arg("minutes", move |_, _| self.minutes.into());
+        text.resolve(&(), env);
+        text.localized_str().to_string()
+    }
+}
+
+/// The placeholder of `time-left`, for where there is no `Env` to localize by.
+impl fmt::Display for TimeLeft {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let unit = |count, one, other| if count == 1 { one } else { other };
+        if self.hours > 0 {
+            write!(f, "{} {} ", self.hours, unit(self.hours, "hour", "hours"))?;
+        }
+        write!(
+            f,
+            "{} {}",
+            self.minutes,
+            unit(self.minutes, "minute", "minutes")
+        )
+    }
+}
+
+/// The time left shown under the rows of the main window: of the waqt now, of
+/// a waqt named for being other than the row it seems, or until a waqt starts.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Remaining {
+    InWaqt(TimeLeft),
+    OfWaqt(TimeLeft, &'static str),
+    ToWaqt(TimeLeft, &'static str),
+}
+
+impl Remaining {
+    pub fn time_left(self) -> TimeLeft {
+        match self {
+            Remaining::InWaqt(time_left)
+            | Remaining::OfWaqt(time_left, _)
+            | Remaining::ToWaqt(time_left, _) => time_left,
+        }
+    }
+
+    /// The `remaining-in-waqt`, `remaining-of-waqt` or `remaining-to-waqt`
+    /// message, with the time left told by `time-left`.
+    pub fn localized(self, env: &Env) -> String {
+        let (key, prayer) = match self {
+            Remaining::InWaqt(_) => ("remaining-in-waqt", ""),
+            Remaining::OfWaqt(_, prayer) => ("remaining-of-waqt", prayer),
+            Remaining::ToWaqt(_, prayer) => ("remaining-to-waqt", prayer),
+        };
+        let time = self.time_left().localized(env);
+        let mut text = LocalizedString::<()>::new(key)
+            .with_placeholder(self.to_string())
+            .with_arg("time", move |_, _| time.clone().into())
+            .with_arg("prayer", move |_, _| prayer.into());
+        text.resolve(&(), env);
+        text.localized_str().to_string()
+    }
+}
+
+impl fmt::Display for Remaining {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Remaining::InWaqt(time_left) => write!(f, "{} remaining", time_left),
+            Remaining::OfWaqt(time_left, prayer) => {
+                write!(f, "{} remaining of {}", time_left, prayer)
+            }
+            Remaining::ToWaqt(time_left, prayer) => {
+                write!(f, "{} remaining to {}", time_left, prayer)
+            }
+        }
     }
 }
 
@@ -0,0 +1,288 @@
+//! Low precision solar and lunar ephemerides.
+//!
+//! Formulae follow Meeus' *Astronomical Algorithms* and the low precision
+//! series of the *Astronomical Almanac*; they are good to a fraction of a degree
+//! which is plenty for calendar and visibility purposes.
+
+use chrono::{DateTime, Duration, NaiveDate, TimeZone, Utc};
+
+pub const MEAN_SYNODIC_MONTH: f64 = 29.530588853;
+
+const J2000: f64 = 2451545.0;
+const UNIX_EPOCH_JD: f64 = 2440587.5;
+
+pub fn julian_day(time: DateTime<Utc>) -> f64 {
+    time.timestamp_millis() as f64 / 86_400_000.0 + UNIX_EPOCH_JD
+}
+
+pub fn from_julian_day(jd: f64) -> DateTime<Utc> {
+    Utc.timestamp_millis(((jd - UNIX_EPOCH_JD) * 86_400_000.0).round() as i64)
+}
+
+fn centuries_since_j2000(jd: f64) -> f64 {
+    (jd - J2000) / 36525.0
+}
+
+fn sin_deg(deg: f64) -> f64 {
+    deg.to_radians().sin()
+}
+
+fn cos_deg(deg: f64) -> f64 {
+    deg.to_radians().cos()
+}
+
+fn normalize_deg(deg: f64) -> f64 {
+    deg.rem_euclid(360.0)
+}
+
+/// Position on the celestial sphere, in degrees.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Equatorial {
+    pub right_ascension: f64,
+    pub declination: f64,
+}
+
+/// Position in the local sky, in degrees. Azimuth is measured eastwards from north.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Horizontal {
+    pub altitude: f64,
+    pub azimuth: f64,
+}
+
+fn obliquity(t: f64) -> f64 {
+    23.439291 - 0.0130042 * t
+}
+
+fn ecliptic_to_equatorial(longitude: f64, latitude: f64, t: f64) -> Equatorial {
+    let e = obliquity(t);
+    let declination = (sin_deg(latitude) * cos_deg(e)
+        + cos_deg(latitude) * sin_deg(e) * sin_deg(longitude))
+    .asin()
+    .to_degrees();
+    let right_ascension = (sin_deg(longitude) * cos_deg(e)
+        - latitude.to_radians().tan() * sin_deg(e))
+    .atan2(cos_deg(longitude))
+    .to_degrees();
+    Equatorial {
+        right_ascension: normalize_deg(right_ascension),
+        declination,
+    }
+}
+
+/// Apparent ecliptic longitude of the sun, in degrees.
+pub fn sun_longitude(time: DateTime<Utc>) -> f64 {
+    let n = julian_day(time) - J2000;
+    let mean_longitude = 280.460 + 0.9856474 * n;
+    let mean_anomaly = 357.528 + 0.9856003 * n;
+    normalize_deg(
+        mean_longitude + 1.915 * sin_deg(mean_anomaly) + 0.020 * sin_deg(2.0 * mean_anomaly),
+    )
+}
+
+pub fn sun_position(time: DateTime<Utc>) -> Equatorial {
+    ecliptic_to_equatorial(
+        sun_longitude(time),
+        0.0,
+        centuries_since_j2000(julian_day(time)),
+    )
+}
+
+/// Geocentric ecliptic longitude and latitude of the moon and its horizontal
+/// parallax, all in degrees.
+pub fn moon_ecliptic(time: DateTime<Utc>) -> (f64, f64, f64) {
+    let t = centuries_since_j2000(julian_day(time));
+
+    let longitude = 218.32 + 481267.881 * t + 6.29 * sin_deg(135.0 + 477198.87 * t)
+        - 1.27 * sin_deg(259.3 - 413335.36 * t)
+        + 0.66 * sin_deg(235.7 + 890534.22 * t)
+        + 0.21 * sin_deg(269.9 + 954397.74 * t)
+        - 0.19 * sin_deg(357.5 + 35999.05 * t)
+        - 0.11 * sin_deg(186.5 + 966404.03 * t);
+    let latitude = 5.13 * sin_deg(93.3 + 483202.02 * t) + 0.28 * sin_deg(228.2 + 960400.89 * t)
+        - 0.28 * sin_deg(318.3 + 6003.15 * t)
+        - 0.17 * sin_deg(217.6 - 407332.21 * t);
+    let parallax = 0.9508
+        + 0.0518 * cos_deg(135.0 + 477198.87 * t)
+        + 0.0095 * cos_deg(259.3 - 413335.36 * t)
+        + 0.0078 * cos_deg(235.7 + 890534.22 * t)
+        + 0.0028 * cos_deg(269.9 + 954397.74 * t);
+
+    (normalize_deg(longitude), latitude, parallax)
+}
+
+pub fn moon_position(time: DateTime<Utc>) -> Equatorial {
+    let (longitude, latitude, _) = moon_ecliptic(time);
+    ecliptic_to_equatorial(longitude, latitude, centuries_since_j2000(julian_day(time)))
+}
+
+fn local_sidereal_time(time: DateTime<Utc>, longitude: f64) -> f64 {
+    normalize_deg(280.46061837 + 360.98564736629 * (julian_day(time) - J2000) + longitude)
+}
+
+pub fn to_horizontal(
+    position: Equatorial,
+    time: DateTime<Utc>,
+    coordinates: adhaan::Coordinates,
+) -> Horizontal {
+    let hour_angle = local_sidereal_time(time, coordinates.longitude) - position.right_ascension;
+    let latitude = coordinates.latitude;
+
+    let altitude = (sin_deg(latitude) * sin_deg(position.declination)
+        + cos_deg(latitude) * cos_deg(position.declination) * cos_deg(hour_angle))
+    .asin()
+    .to_degrees();
+    let azimuth = sin_deg(hour_angle)
+        .atan2(
+            cos_deg(hour_angle) * sin_deg(latitude)
+                - position.declination.to_radians().tan() * cos_deg(latitude),
+        )
+        .to_degrees()
+        + 180.0;
+
+    Horizontal {
+        altitude,
+        azimuth: normalize_deg(azimuth),
+    }
+}
+
+pub fn sun_horizontal(time: DateTime<Utc>, coordinates: adhaan::Coordinates) -> Horizontal {
+    to_horizontal(sun_position(time), time, coordinates)
+}
+
+/// Topocentric position of the moon, corrected for parallax.
+pub fn moon_horizontal(time: DateTime<Utc>, coordinates: adhaan::Coordinates) -> Horizontal {
+    let (_, _, parallax) = moon_ecliptic(time);
+    let geocentric = to_horizontal(moon_position(time), time, coordinates);
+    Horizontal {
+        altitude: geocentric.altitude - parallax * cos_deg(geocentric.altitude),
+        ..geocentric
+    }
+}
+
+/// Altitude of the centre at the moment the upper limb touches the horizon,
+/// accounting for refraction. The mean semidiameters of the sun and the moon
+/// are close enough for one value to serve both, given topocentric altitudes.
+pub const HORIZON: f64 = -0.833;
+
+/// Finds the times `altitude_at` crosses `horizon` in the 24 hours starting at
+/// `from`, as `(rising, setting)`.
+pub fn crossings(
+    from: DateTime<Utc>,
+    horizon: f64,
+    altitude_at: impl Fn(DateTime<Utc>) -> f64,
+) -> (Option<DateTime<Utc>>, Option<DateTime<Utc>>) {
+    const STEP_MINUTES: i64 = 10;
+
+    let mut rising = None;
+    let mut setting = None;
+
+    let mut previous_time = from;
+    let mut previous = altitude_at(from) - horizon;
+    for step in 1..=(24 * 60 / STEP_MINUTES) {
+        let time = from + Duration::minutes(step * STEP_MINUTES);
+        let current = altitude_at(time) - horizon;
+
+        if previous.signum() != current.signum() {
+            let (mut low, mut high) = (previous_time, time);
+            for _ in 0..12 {
+                let mid = low + (high - low) / 2;
+                if (altitude_at(mid) - horizon).signum() == previous.signum() {
+                    low = mid;
+                } else {
+                    high = mid;
+                }
+            }
+            if current > 0.0 {
+                rising.get_or_insert(high);
+            } else {
+                setting.get_or_insert(high);
+            }
+        }
+
+        previous_time = time;
+        previous = current;
+    }
+
+    (rising, setting)
+}
+
+/// Start of the local mean solar day of `date` at `coordinates`.
+pub fn local_midnight(date: NaiveDate, coordinates: adhaan::Coordinates) -> DateTime<Utc> {
+    Utc.from_utc_date(&date).and_hms(0, 0, 0)
+        - Duration::minutes((coordinates.longitude * 4.0) as _)
+}
+
+/// Local mean solar date at `coordinates` at `time`.
+pub fn local_date(time: DateTime<Utc>, coordinates: adhaan::Coordinates) -> NaiveDate {
+    (time + Duration::minutes((coordinates.longitude * 4.0) as _))
+        .date()
+        .naive_utc()
+}
+
+/// Sunset at `coordinates` on the local day of `date`.
+pub fn sunset(date: NaiveDate, coordinates: adhaan::Coordinates) -> Option<DateTime<Utc>> {
+    crossings(local_midnight(date, coordinates), HORIZON, |time| {
+        sun_horizontal(time, coordinates).altitude
+    })
+    .1
+}
+
+/// Time of the new moon of lunation `k`, counted from the one of 6 January 2000.
+pub fn new_moon(k: i64) -> DateTime<Utc> {
+    let k = k as f64;
+    let t = k / 1236.85;
+    let t2 = t * t;
+    let t3 = t2 * t;
+    let t4 = t3 * t;
+
+    let jde =
+        2451550.09766 + 29.530588861 * k + 0.00015437 * t2 - 0.000000150 * t3 + 0.00000000073 * t4;
+
+    let e = 1.0 - 0.002516 * t - 0.0000074 * t2;
+    let m = 2.5534 + 29.10535670 * k - 0.0000014 * t2 - 0.00000011 * t3;
+    let mp = 201.5643 + 385.81693528 * k + 0.0107582 * t2 + 0.00001238 * t3 - 0.000000058 * t4;
+    let f = 160.7108 + 390.67050284 * k - 0.0016118 * t2 - 0.00000227 * t3 + 0.000000011 * t4;
+    let omega = 124.7746 - 1.56375588 * k + 0.0020672 * t2 + 0.00000215 * t3;
+
+    let correction = -0.40720 * sin_deg(mp)
+        + 0.17241 * e * sin_deg(m)
+        + 0.01608 * sin_deg(2.0 * mp)
+        + 0.01039 * sin_deg(2.0 * f)
+        + 0.00739 * e * sin_deg(mp - m)
+        - 0.00514 * e * sin_deg(mp + m)
+        + 0.00208 * e * e * sin_deg(2.0 * m)
+        - 0.00111 * sin_deg(mp - 2.0 * f)
+        - 0.00057 * sin_deg(mp + 2.0 * f)
+        + 0.00056 * e * sin_deg(2.0 * mp + m)
+        - 0.00042 * sin_deg(3.0 * mp)
+        + 0.00042 * e * sin_deg(m + 2.0 * f)
+        + 0.00038 * e * sin_deg(m - 2.0 * f)
+        - 0.00024 * e * sin_deg(2.0 * mp - m)
+        - 0.00017 * sin_deg(omega)
+        - 0.00007 * sin_deg(mp + 2.0 * m)
+        + 0.00004 * sin_deg(2.0 * mp - 2.0 * f)
+        + 0.00004 * sin_deg(3.0 * m)
+        + 0.00003 * sin_deg(mp + m - 2.0 * f)
+        + 0.00003 * sin_deg(2.0 * mp + 2.0 * f)
+        - 0.00003 * sin_deg(mp + m + 2.0 * f)
+        + 0.00003 * sin_deg(mp - m + 2.0 * f)
+        - 0.00002 * sin_deg(mp - m - 2.0 * f)
+        - 0.00002 * sin_deg(3.0 * mp + m)
+        + 0.00002 * sin_deg(4.0 * mp);
+
+    // ΔT is a bit over a minute these days, which is below the precision we need
+    // but cheap to account for.
+    from_julian_day(jde + correction) - Duration::seconds(69)
+}
+
+/// Lunation number, as used by [`new_moon`], of the last new moon at or before `time`.
+pub fn lunation_at(time: DateTime<Utc>) -> i64 {
+    let mut k = ((julian_day(time) - 2451550.09766) / MEAN_SYNODIC_MONTH).floor() as i64;
+    while new_moon(k) > time {
+        k -= 1;
+    }
+    while new_moon(k + 1) <= time {
+        k += 1;
+    }
+    k
+}
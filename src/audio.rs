@@ -0,0 +1,100 @@
+//! Playback of the adhan and of the chime for the other waqts.
+
+use std::{cell::RefCell, fs::File, io::BufReader, time::Duration};
+
+use adhaan::Prayer;
+use rodio::{source::SineWave, Decoder, OutputStream, Sink, Source};
+
+use crate::config::{AudioConfig, Sound};
+
+struct Player {
+    _stream: OutputStream,
+    sink: Sink,
+}
+
+thread_local! {
+    /// The output stream is not `Send`, so it stays with the UI thread that
+    /// starts and stops playback.
+    static PLAYER: RefCell<Option<Player>> = RefCell::new(None);
+}
+
+/// Starts playing `sound` for `prayer`, replacing whatever is playing, and
+/// returns whether it did.
+pub fn play(sound: Sound, prayer: Prayer, config: &AudioConfig) -> bool {
+    match try_play(sound, prayer, config) {
+        Ok(()) => true,
+        Err(e) => {
+            tracing::error!("Failed to play sound: {:?}", e);
+            false
+        }
+    }
+}
+
+/// Plays the chime once at `volume`, from 0 to 1, for the Fajr alarm.
+pub fn ring(volume: f32) {
+    if let Err(e) = start(volume, |sink| {
+        append_chime(sink);
+        Ok(())
+    }) {
+        tracing::error!("Failed to ring alarm: {:?}", e);
+    }
+}
+
+fn try_play(sound: Sound, prayer: Prayer, config: &AudioConfig) -> anyhow::Result<()> {
+    if sound == Sound::Off {
+        return Ok(());
+    }
+
+    start(config.volume as f32 / 100.0, |sink| {
+        match (sound, config.adhan_for(prayer)) {
+            (Sound::Adhan, Some(path)) => sink.append(
+                Decoder::new(BufReader::new(File::open(path)?))?
+                    .fade_in(Duration::from_secs(config.fade_in as _)),
+            ),
+            // Without an adhan file there is at least something to hear.
+            _ => append_chime(sink),
+        }
+        Ok(())
+    })
+}
+
+/// Replaces whatever is playing with what `append` puts into a new sink.
+fn start(volume: f32, append: impl FnOnce(&Sink) -> anyhow::Result<()>) -> anyhow::Result<()> {
+    let (stream, handle) = OutputStream::try_default()?;
+    let sink = Sink::try_new(&handle)?;
+    sink.set_volume(volume);
+    append(&sink)?;
+
+    PLAYER.with(|player| {
+        *player.borrow_mut() = Some(Player {
+            _stream: stream,
+            sink,
+        })
+    });
+    Ok(())
+}
+
+/// Two falling tones, generated so that no sound file has to be shipped.
+fn append_chime(sink: &Sink) {
+    for (frequency, millis) in [(880.0, 400), (660.0, 900)] {
+        sink.append(
+            SineWave::new(frequency)
+                .take_duration(Duration::from_millis(millis))
+                .fade_in(Duration::from_millis(10))
+                .amplify(0.25),
+        );
+    }
+}
+
+pub fn stop() {
+    PLAYER.with(|player| player.borrow_mut().take());
+}
+
+pub fn is_playing() -> bool {
+    PLAYER.with(|player| {
+        player
+            .borrow()
+            .as_ref()
+            .map_or(false, |player| !player.sink.empty())
+    })
+}
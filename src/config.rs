@@ -1,6 +1,11 @@
+use std::{fmt, path::PathBuf, str::FromStr};
+
+use chrono::{Datelike, Duration, NaiveDate, NaiveDateTime, NaiveTime, Weekday};
 use druid::Data;
 use serde::{Deserialize, Serialize};
 
+use crate::hijri::HijriCalendar;
+
 #[derive(Debug, Serialize, Deserialize, PartialEq, Clone, Data)]
 pub struct Config {
     pub font: String,
@@ -21,13 +26,55 @@ pub struct Config {
     #[serde(default)]
     #[data(same_fn = "PartialEq::eq")]
     pub user_adjustments: adhaan::TimeAdjustment,
+
+    #[serde(default)]
+    pub hijri: HijriConfig,
+
+    #[serde(default)]
+    pub events: EventsConfig,
+
+    #[serde(default)]
+    pub fasting: FastingConfig,
+
+    #[serde(default)]
+    pub travel: TravelConfig,
+
+    #[serde(default)]
+    pub notifications: NotificationsConfig,
+
+    #[serde(default)]
+    pub audio: AudioConfig,
+
+    #[serde(default)]
+    pub fajr_alarm: FajrAlarmConfig,
+
+    #[serde(default)]
+    pub hooks: HooksConfig,
+
+    #[serde(default)]
+    pub webhooks: WebhooksConfig,
+
+    #[serde(default)]
+    pub quiet: QuietConfig,
+
+    #[serde(default)]
+    pub calendar: CalendarConfig,
+
+    #[serde(default)]
+    pub print: PrintConfig,
 }
 
 impl Config {
     pub fn load() -> anyhow::Result<Config> {
         let config_path = crate::utils::config_path();
         if config_path.exists() {
-            Ok(toml::from_slice(&std::fs::read(config_path)?)?)
+            let mut config: Config = toml::from_slice(&std::fs::read(config_path)?)?;
+            // A hand-edited offset is kept within what the settings allow.
+            config.hijri.offset = config
+                .hijri
+                .offset
+                .clamp(-MAX_HIJRI_OFFSET, MAX_HIJRI_OFFSET);
+            Ok(config)
         } else {
             let config = Config::default();
             let config_toml = toml::to_string(&config).unwrap();
@@ -36,6 +83,11 @@ impl Config {
         }
     }
 
+    pub fn save(&self) -> anyhow::Result<()> {
+        std::fs::write(crate::utils::config_path(), toml::to_string(self)?)?;
+        Ok(())
+    }
+
     pub fn apply_appearance_to_env(&self, env: &mut druid::Env) {
         use crate::ui_main::color;
         use druid::{theme, Color};
@@ -65,6 +117,7 @@ impl Config {
             env.set(color::ELAPSED_CRITICAL, Color::from_rgba32_u32(0xFFB492_FF));
             env.set(color::ELAPSED_OKAY, Color::from_rgba32_u32(0xFFDC61_FF));
             env.set(color::REMAINING, Color::from_rgba32_u32(0x00FFA6_FF));
+            env.set(color::MOON_LIT, Color::from_rgba32_u32(0xF2D64B_FF));
 
             env.set(color::CLOSE_HOT, Color::rgb8(216, 44, 29));
             env.set(color::CLOSE_ACTIVE, Color::rgb8(196, 43, 28));
@@ -72,6 +125,7 @@ impl Config {
             env.set(color::ELAPSED_CRITICAL, Color::from_rgba32_u32(0x7B2E15_FF));
             env.set(color::ELAPSED_OKAY, Color::from_rgba32_u32(0x735C00_FF));
             env.set(color::REMAINING, Color::from_rgba32_u32(0x006008_FF));
+            env.set(color::MOON_LIT, Color::from_rgba32_u32(0xF4EBC3_FF));
 
             env.set(color::CLOSE_HOT, Color::rgb8(196, 43, 28));
             env.set(color::CLOSE_ACTIVE, Color::rgb8(178, 42, 27));
@@ -102,6 +156,544 @@ impl Default for Config {
             location_name: Some("Kaaba".into()),
 
             user_adjustments: adhaan::TimeAdjustment::default(),
+
+            hijri: HijriConfig::default(),
+            events: EventsConfig::default(),
+            fasting: FastingConfig::default(),
+            travel: TravelConfig::default(),
+            notifications: NotificationsConfig::default(),
+            audio: AudioConfig::default(),
+            fajr_alarm: FajrAlarmConfig::default(),
+            hooks: HooksConfig::default(),
+            webhooks: WebhooksConfig::default(),
+            quiet: QuietConfig::default(),
+            calendar: CalendarConfig::default(),
+            print: PrintConfig::default(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone, Copy, Data)]
+#[serde(default)]
+pub struct HijriConfig {
+    pub calendar: HijriCalendar,
+    /// Days to add for the local moon sighting, within `±MAX_HIJRI_OFFSET`.
+    pub offset: i8,
+}
+
+pub const MAX_HIJRI_OFFSET: i8 = 2;
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone, Copy, Data)]
+#[serde(default)]
+pub struct EventsConfig {
+    pub mawlid: bool,
+    /// Minutes after Maghrib on the evening before an event to remind at.
+    #[serde(with = "event_reminder")]
+    pub reminder: Option<u16>,
+}
+
+pub const DEFAULT_EVENT_REMINDER: u16 = 30;
+
+/// The event reminder is on unless set to `false`, as a missing one defaults.
+mod event_reminder {
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    #[derive(Serialize, Deserialize)]
+    #[serde(untagged)]
+    enum Reminder {
+        Minutes(u16),
+        On(bool),
+    }
+
+    pub fn serialize<S: Serializer>(
+        reminder: &Option<u16>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        match *reminder {
+            Some(minutes) => Reminder::Minutes(minutes),
+            None => Reminder::On(false),
+        }
+        .serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<u16>, D::Error> {
+        Ok(match Reminder::deserialize(deserializer)? {
+            Reminder::Minutes(minutes) => Some(minutes),
+            Reminder::On(on) => on.then(|| super::DEFAULT_EVENT_REMINDER),
+        })
+    }
+}
+
+impl Default for EventsConfig {
+    fn default() -> Self {
+        EventsConfig {
+            mawlid: false,
+            reminder: Some(DEFAULT_EVENT_REMINDER),
+        }
+    }
+}
+
+impl Default for HijriConfig {
+    fn default() -> Self {
+        HijriConfig {
+            calendar: HijriCalendar::UmmAlQura,
+            offset: 0,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone, Copy, Data)]
+#[serde(default)]
+pub struct FastingConfig {
+    pub mondays_thursdays: bool,
+    pub ayyam_al_bid: bool,
+    pub arafah: bool,
+    /// Ashura along with Tasu'a before it.
+    pub ashura: bool,
+    pub six_of_shawwal: bool,
+    /// Minutes after Isha on the evening before a fast to remind at.
+    pub after_isha: u16,
+}
+
+pub const DEFAULT_FASTING_REMINDER: u16 = 60;
+
+impl Default for FastingConfig {
+    fn default() -> Self {
+        FastingConfig {
+            mondays_thursdays: false,
+            ayyam_al_bid: true,
+            arafah: true,
+            ashura: true,
+            six_of_shawwal: true,
+            after_isha: DEFAULT_FASTING_REMINDER,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone, Copy, Data, Default)]
+#[serde(default)]
+pub struct TravelConfig {
+    pub enabled: bool,
+    #[data(same_fn = "PartialEq::eq")]
+    pub since: Option<NaiveDate>,
+    /// Days after which travel mode switches itself off.
+    pub auto_off_after: Option<u8>,
+}
+
+pub const DEFAULT_TRAVEL_DAYS: u8 = 4;
+
+impl TravelConfig {
+    pub fn set_enabled(&mut self, enabled: bool, today: NaiveDate) {
+        self.enabled = enabled;
+        self.since = enabled.then(|| today);
+    }
+
+    /// Switches travel mode off if it has been on for long enough, returning
+    /// whether it did.
+    pub fn expire(&mut self, today: NaiveDate) -> bool {
+        match (self.enabled, self.since, self.auto_off_after) {
+            (true, Some(since), Some(days)) if today - since >= Duration::days(days as _) => {
+                self.set_enabled(false, today);
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+/// A setting for each row of the main window.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone, Copy, Data)]
+pub struct PerPrayer<T> {
+    pub fajr: T,
+    pub sunrise: T,
+    pub dhuhr: T,
+    pub asr: T,
+    pub maghrib: T,
+    pub isha: T,
+    pub qiyam: T,
+}
+
+impl<T> PerPrayer<T> {
+    pub fn get(&self, prayer: adhaan::Prayer) -> &T {
+        use adhaan::Prayer::*;
+
+        match prayer {
+            Fajr | Tomorrow => &self.fajr,
+            Sunrise => &self.sunrise,
+            Dhuhr => &self.dhuhr,
+            AsrAwwal | AsrThaani => &self.asr,
+            Maghrib => &self.maghrib,
+            Isha | Yesterday => &self.isha,
+            Qiyam | QiyamYesterday => &self.qiyam,
+        }
+    }
+}
+
+impl<T: Clone> PerPrayer<T> {
+    /// `value` for the five daily prayers and `other` for sunrise and qiyam.
+    pub fn five_and_others(value: T, other: T) -> PerPrayer<T> {
+        PerPrayer {
+            fajr: value.clone(),
+            sunrise: other.clone(),
+            dhuhr: value.clone(),
+            asr: value.clone(),
+            maghrib: value.clone(),
+            isha: value,
+            qiyam: other,
+        }
+    }
+}
+
+/// Minutes before a waqt to remind at, written as a comma separated list.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone, Default)]
+#[serde(transparent)]
+pub struct Offsets(pub Vec<u16>);
+
+impl Data for Offsets {
+    fn same(&self, other: &Self) -> bool {
+        self == other
+    }
+}
+
+impl FromStr for Offsets {
+    type Err = std::num::ParseIntError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut offsets = s
+            .split(',')
+            .map(str::trim)
+            .filter(|offset| !offset.is_empty())
+            .map(str::parse)
+            .collect::<Result<Vec<_>, _>>()?;
+        offsets.sort_unstable_by(|a, b| b.cmp(a));
+        offsets.dedup();
+        Ok(Offsets(offsets))
+    }
+}
+
+impl fmt::Display for Offsets {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, offset) in self.0.iter().enumerate() {
+            if i > 0 {
+                f.write_str(", ")?;
+            }
+            write!(f, "{}", offset)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone, Data)]
+#[serde(default)]
+pub struct NotificationsConfig {
+    pub at_start: PerPrayer<bool>,
+    pub before_start: PerPrayer<Offsets>,
+    /// Whether to alert once `critical_at` minutes remain.
+    pub critical: PerPrayer<bool>,
+    pub popup: PopupConfig,
+}
+
+/// When to bring up the main window by itself.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone, Copy, Data)]
+#[serde(default)]
+pub struct PopupConfig {
+    pub at_start: bool,
+    pub at_critical: bool,
+    /// Seconds after which to hide it again, if it is not dismissed before.
+    pub hide_after: Option<u16>,
+}
+
+pub const DEFAULT_POPUP_HIDE_AFTER: u16 = 60;
+
+impl Default for PopupConfig {
+    fn default() -> Self {
+        PopupConfig {
+            at_start: false,
+            at_critical: false,
+            hide_after: Some(DEFAULT_POPUP_HIDE_AFTER),
+        }
+    }
+}
+
+impl Default for NotificationsConfig {
+    fn default() -> Self {
+        NotificationsConfig {
+            at_start: PerPrayer::five_and_others(true, false),
+            before_start: PerPrayer::five_and_others(Offsets::default(), Offsets::default()),
+            critical: PerPrayer::five_and_others(true, false),
+            popup: PopupConfig::default(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone, Copy, Data)]
+pub enum Sound {
+    Off,
+    Chime,
+    Adhan,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone, Data)]
+#[serde(default)]
+pub struct AudioConfig {
+    /// Audio file of the adhan, in WAV, OGG, MP3 or FLAC.
+    #[data(same_fn = "PartialEq::eq")]
+    pub adhan: Option<PathBuf>,
+    /// Audio file of the adhan of Fajr, if it differs.
+    #[data(same_fn = "PartialEq::eq")]
+    pub fajr_adhan: Option<PathBuf>,
+    pub at_start: PerPrayer<Sound>,
+    /// Whether to announce the start of a waqt by text-to-speech.
+    pub speak: PerPrayer<bool>,
+    /// Percent of the system volume.
+    pub volume: u8,
+    /// Seconds over which the adhan fades in.
+    pub fade_in: u8,
+    /// Whether to pause media players when the adhan or a reminder of a waqt
+    /// comes.
+    pub pause_media: bool,
+    /// Whether to resume them once the adhan ends or the reminder is acted on.
+    pub resume_media: bool,
+}
+
+pub const DEFAULT_VOLUME: u8 = 80;
+pub const DEFAULT_FADE_IN: u8 = 3;
+
+impl AudioConfig {
+    pub fn adhan_for(&self, prayer: adhaan::Prayer) -> Option<&PathBuf> {
+        match prayer {
+            adhaan::Prayer::Fajr | adhaan::Prayer::Tomorrow => {
+                self.fajr_adhan.as_ref().or(self.adhan.as_ref())
+            }
+            _ => self.adhan.as_ref(),
+        }
+    }
+}
+
+impl Default for AudioConfig {
+    fn default() -> Self {
+        AudioConfig {
+            adhan: None,
+            fajr_adhan: None,
+            at_start: PerPrayer {
+                sunrise: Sound::Chime,
+                qiyam: Sound::Off,
+                ..PerPrayer::five_and_others(Sound::Adhan, Sound::Off)
+            },
+            speak: PerPrayer::five_and_others(false, false),
+            volume: DEFAULT_VOLUME,
+            fade_in: DEFAULT_FADE_IN,
+            pause_media: false,
+            resume_media: true,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone, Copy, Data)]
+#[serde(default)]
+pub struct FajrAlarmConfig {
+    pub enabled: bool,
+    /// Minutes after Fajr to ring at, negative for before.
+    pub offset: i16,
+    /// Minutes to snooze for.
+    pub snooze: u8,
+    /// Whether dismissing needs a sum solved, to be sure one is awake.
+    pub puzzle: bool,
+}
+
+pub const DEFAULT_SNOOZE: u8 = 5;
+
+impl Default for FajrAlarmConfig {
+    fn default() -> Self {
+        FajrAlarmConfig {
+            enabled: false,
+            offset: 0,
+            snooze: DEFAULT_SNOOZE,
+            puzzle: false,
+        }
+    }
+}
+
+/// What goes into exported iCalendar files and the calendar feed.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone, Copy, Default, Data)]
+#[serde(default)]
+pub struct CalendarConfig {
+    /// Minutes before each prayer for calendar apps to remind at.
+    pub reminder: Option<u16>,
+    /// Local port to serve a rolling calendar feed on.
+    pub feed: Option<u16>,
+}
+
+pub const DEFAULT_CALENDAR_REMINDER: u16 = 10;
+pub const DEFAULT_FEED_PORT: u16 = 8642;
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone, Copy, Data)]
+pub enum Paper {
+    A4,
+    Letter,
+}
+
+impl Paper {
+    /// Width and height in millimetres, standing.
+    pub fn size(self) -> (f64, f64) {
+        match self {
+            Paper::A4 => (210.0, 297.0),
+            Paper::Letter => (215.9, 279.4),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone, Copy, Data)]
+pub enum Orientation {
+    Portrait,
+    Landscape,
+}
+
+/// How printed timetables are laid out.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone, Data)]
+#[serde(default)]
+pub struct PrintConfig {
+    pub paper: Paper,
+    pub orientation: Orientation,
+    /// An image to head every page with.
+    #[data(same_fn = "PartialEq::eq")]
+    pub logo: Option<PathBuf>,
+}
+
+impl PrintConfig {
+    /// Width and height of a page in millimetres.
+    pub fn page_size(&self) -> (f64, f64) {
+        let (width, height) = self.paper.size();
+        match self.orientation {
+            Orientation::Portrait => (width, height),
+            Orientation::Landscape => (height, width),
+        }
+    }
+}
+
+impl Default for PrintConfig {
+    fn default() -> Self {
+        PrintConfig {
+            paper: Paper::A4,
+            orientation: Orientation::Portrait,
+            logo: None,
+        }
+    }
+}
+
+/// Shell commands to run on prayer events, given the details in `ADHAAN_*`
+/// environment variables.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone, Default, Data)]
+#[serde(default)]
+pub struct HooksConfig {
+    #[data(same_fn = "PartialEq::eq")]
+    pub waqt_start: Vec<String>,
+    #[data(same_fn = "PartialEq::eq")]
+    pub pre_reminder: Vec<String>,
+    /// Minutes before the start of a waqt `pre_reminder` hooks and webhooks
+    /// fire at, the same as the reminders under `[notifications]` if unset.
+    #[data(same_fn = "PartialEq::eq")]
+    pub pre_reminder_minutes: Option<Vec<u16>>,
+    #[data(same_fn = "PartialEq::eq")]
+    pub critical: Vec<String>,
+    #[data(same_fn = "PartialEq::eq")]
+    pub waqt_end: Vec<String>,
+    #[data(same_fn = "PartialEq::eq")]
+    pub day_rollover: Vec<String>,
+}
+
+impl HooksConfig {
+    pub fn get(&self, event: crate::hooks::HookEvent) -> &[String] {
+        use crate::hooks::HookEvent;
+
+        match event {
+            HookEvent::WaqtStart => &self.waqt_start,
+            HookEvent::PreReminder(_) => &self.pre_reminder,
+            HookEvent::Critical => &self.critical,
+            HookEvent::WaqtEnd => &self.waqt_end,
+            HookEvent::DayRollover => &self.day_rollover,
+        }
+    }
+}
+
+/// When alerts should make no sound, showing silent notifications instead.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone, Data)]
+#[serde(default)]
+pub struct QuietConfig {
+    #[data(same_fn = "PartialEq::eq")]
+    pub hours: Vec<QuietHours>,
+    pub follow_do_not_disturb: bool,
+}
+
+impl QuietConfig {
+    pub fn is_quiet_at(&self, now: NaiveDateTime) -> bool {
+        self.hours.iter().any(|hours| hours.contain(now))
+    }
+}
+
+impl Default for QuietConfig {
+    fn default() -> Self {
+        QuietConfig {
+            hours: vec![],
+            follow_do_not_disturb: true,
+        }
+    }
+}
+
+/// Daily quiet hours, which may span midnight.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone)]
+pub struct QuietHours {
+    pub from: NaiveTime,
+    pub to: NaiveTime,
+    /// Days they start on, every day when empty.
+    #[serde(default)]
+    pub days: Vec<Weekday>,
+}
+
+impl QuietHours {
+    fn contain(&self, now: NaiveDateTime) -> bool {
+        let starts_on =
+            |date: NaiveDate| self.days.is_empty() || self.days.contains(&date.weekday());
+        let (date, time) = (now.date(), now.time());
+        if self.from <= self.to {
+            starts_on(date) && self.from <= time && time < self.to
+        } else {
+            (starts_on(date) && self.from <= time) || (starts_on(date.pred()) && time < self.to)
+        }
+    }
+}
+
+/// URLs to post JSON to on prayer events.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone, Data)]
+#[serde(default)]
+pub struct WebhooksConfig {
+    #[data(same_fn = "PartialEq::eq")]
+    pub urls: Vec<String>,
+    /// Names of the events to post, as the keys of `HooksConfig`.
+    #[data(same_fn = "PartialEq::eq")]
+    pub events: Vec<String>,
+    /// Attempts after the first failed one.
+    pub retries: u8,
+}
+
+pub const DEFAULT_WEBHOOK_RETRIES: u8 = 3;
+
+impl WebhooksConfig {
+    pub fn is_subscribed(&self, event: crate::hooks::HookEvent) -> bool {
+        !self.urls.is_empty() && self.events.iter().any(|name| name == event.name())
+    }
+}
+
+impl Default for WebhooksConfig {
+    fn default() -> Self {
+        WebhooksConfig {
+            urls: vec![],
+            events: ["waqt_start", "pre_reminder", "critical", "waqt_end"]
+                .map(String::from)
+                .to_vec(),
+            retries: DEFAULT_WEBHOOK_RETRIES,
         }
     }
 }
@@ -130,7 +722,41 @@ pub enum AvailableMethods {
     MoonsightingCommitteeWhiteIsha,
 }
 
+/// The methods offered in the settings, by their names.
+pub const AVAILABLE_METHODS: [(&str, AvailableMethods); 12] = {
+    use AvailableMethods::*;
+    [
+        ("Dubai", Dubai),
+        ("Egyptian", Egyptian),
+        ("Karachi", Karachi),
+        ("Kuwait", Kuwait),
+        ("Muslim World League", MuslimWorldLeague),
+        ("Islamic Society North America", NorthAmerica),
+        ("Qatar", Qatar),
+        ("Singapore", Singapore),
+        ("Umm Al Qura University, Makkah", UmmAlQura),
+        ("Moonsighting Committee", MoonsightingCommittee),
+        (
+            "Moonsighting Committee with red Isha",
+            MoonsightingCommitteeRedIsha,
+        ),
+        (
+            "Moonsighting Committee with white Isha",
+            MoonsightingCommitteeWhiteIsha,
+        ),
+    ]
+};
+
 impl AvailableMethods {
+    /// The name of the method as the settings list it.
+    pub fn label(self) -> &'static str {
+        AVAILABLE_METHODS
+            .iter()
+            .find(|(_, available)| *available == self)
+            .map(|(label, _)| *label)
+            .unwrap()
+    }
+
     pub fn get_parameters(self) -> adhaan::Parameters {
         use adhaan::{prominent_methods::*, Method};
         adhaan::Parameters::new(match self {
@@ -150,7 +776,7 @@ impl AvailableMethods {
     }
 }
 
-mod remote_defs {
+pub mod remote_defs {
     use serde::{Deserialize, Serialize};
 
     #[derive(Serialize, Deserialize)]
@@ -0,0 +1,190 @@
+//! Islamic calendar events, built in and user defined.
+
+use adhaan::Prayer;
+use chrono::{Duration, NaiveDate};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    alerts::{Alert, AlertKind},
+    app_state::AppState,
+    config::Config,
+    hijri::{HijriCalendar, HijriDate},
+};
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone)]
+pub struct HijriEvent {
+    pub name: String,
+    /// `None` for events recurring every month.
+    #[serde(default)]
+    pub month: Option<u8>,
+    pub day: u8,
+    /// Whether the event is observed in the night beginning the day, i.e. from
+    /// the Maghrib before it.
+    #[serde(default)]
+    pub night: bool,
+}
+
+impl HijriEvent {
+    fn new(name: &str, month: Option<u8>, day: u8, night: bool) -> HijriEvent {
+        HijriEvent {
+            name: name.into(),
+            month,
+            day,
+            night,
+        }
+    }
+}
+
+pub fn builtin_events(config: &Config) -> Vec<HijriEvent> {
+    let mut events = vec![
+        HijriEvent::new("Islamic New Year", Some(1), 1, false),
+        HijriEvent::new("Ashura", Some(1), 10, false),
+        HijriEvent::new("Laylat al-Qadr", Some(9), 21, true),
+        HijriEvent::new("Laylat al-Qadr", Some(9), 23, true),
+        HijriEvent::new("Laylat al-Qadr", Some(9), 25, true),
+        HijriEvent::new("Laylat al-Qadr", Some(9), 27, true),
+        HijriEvent::new("Laylat al-Qadr", Some(9), 29, true),
+        HijriEvent::new("Eid al-Fitr", Some(10), 1, false),
+        HijriEvent::new("Day of Arafah", Some(12), 9, false),
+        HijriEvent::new("Eid al-Adha", Some(12), 10, false),
+        HijriEvent::new("Ayyam al-Bid", None, 13, false),
+        HijriEvent::new("Ayyam al-Bid", None, 14, false),
+        HijriEvent::new("Ayyam al-Bid", None, 15, false),
+    ];
+    if config.events.mawlid {
+        events.push(HijriEvent::new("Mawlid an-Nabi", Some(3), 12, false));
+    }
+    events
+}
+
+#[derive(Debug, Serialize, Deserialize, Default)]
+struct UserEvents {
+    #[serde(default)]
+    event: Vec<HijriEvent>,
+}
+
+/// Loads the user defined events from `events.toml`, creating an empty one for
+/// the user to fill if it does not exist.
+pub fn load_user_events() -> anyhow::Result<Vec<HijriEvent>> {
+    let events_path = crate::utils::events_path();
+    if events_path.exists() {
+        Ok(toml::from_slice::<UserEvents>(&std::fs::read(events_path)?)?.event)
+    } else {
+        std::fs::write(
+            events_path,
+            "# Recurring Hijri events, e.g.\n\
+             #\n\
+             # [[event]]\n\
+             # name = \"Anniversary\"\n\
+             # month = 7\n\
+             # day = 27\n\
+             #\n\
+             # `month` may be left out for monthly events and `night = true` marks\n\
+             # events observed from the Maghrib before.\n",
+        )?;
+        Ok(vec![])
+    }
+}
+
+/// An event falling on a civil date.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Occurrence {
+    pub date: NaiveDate,
+    pub hijri: HijriDate,
+    pub event: HijriEvent,
+}
+
+/// Civil date during which `hijri` falls for most of its daylight, with the
+/// user's moon sighting correction applied.
+pub fn civil_date_of(hijri: HijriDate, calendar: HijriCalendar, offset: i8) -> NaiveDate {
+    hijri.to_gregorian(calendar) - Duration::days(offset as _)
+}
+
+/// Occurrences of `events` in the Hijri year `year`, sorted by date.
+pub fn occurrences_in_year(events: &[HijriEvent], year: i32, config: &Config) -> Vec<Occurrence> {
+    let mut occurrences = events
+        .iter()
+        .flat_map(|event| {
+            let months = match event.month {
+                Some(month) => month..=month,
+                None => 1..=12,
+            };
+            months.filter_map(move |month| {
+                let calendar = config.hijri.calendar;
+                (event.day <= HijriDate::days_in_month(year, month, calendar)).then(|| {
+                    let hijri = HijriDate {
+                        year,
+                        month,
+                        day: event.day,
+                    };
+                    Occurrence {
+                        date: civil_date_of(hijri, calendar, config.hijri.offset),
+                        hijri,
+                        event: event.clone(),
+                    }
+                })
+            })
+        })
+        .collect::<Vec<_>>();
+    occurrences.sort_by_key(|occurrence| occurrence.date);
+    occurrences
+}
+
+/// The first occurrence on or after `today`.
+pub fn upcoming(
+    events: &[HijriEvent],
+    today: HijriDate,
+    today_civil: NaiveDate,
+    config: &Config,
+) -> Option<Occurrence> {
+    (today.year..=today.year + 1)
+        .flat_map(|year| occurrences_in_year(events, year, config))
+        .find(|occurrence| occurrence.date >= today_civil)
+}
+
+/// Events falling on the civil date `date`.
+pub fn on_date(events: &[HijriEvent], date: NaiveDate, config: &Config) -> Vec<Occurrence> {
+    let hijri = HijriDate::from_gregorian(
+        date + Duration::days(config.hijri.offset as _),
+        config.hijri.calendar,
+    );
+    events
+        .iter()
+        .filter(|event| event.day == hijri.day && event.month.map_or(true, |m| m == hijri.month))
+        .map(|event| Occurrence {
+            date,
+            hijri,
+            event: event.clone(),
+        })
+        .collect()
+}
+
+/// Reminders on the evening before the events of tomorrow.
+pub fn alerts(data: &AppState) -> Vec<Alert> {
+    let minutes_after_maghrib = match data.config.events.reminder {
+        Some(minutes) => minutes,
+        None => return vec![],
+    };
+
+    let at = data.adjusted_time_of(Prayer::Maghrib) + Duration::minutes(minutes_after_maghrib as _);
+    let tomorrow = at.with_timezone(&chrono::Local).date().naive_local().succ();
+
+    on_date(&data.events(), tomorrow, &data.config)
+        .into_iter()
+        .map(|occurrence| Alert {
+            key: format!("event/{}/{}", occurrence.date, occurrence.event.name),
+            kind: AlertKind::Reminder,
+            at,
+            summary: format!(
+                "{} {}",
+                occurrence.event.name,
+                if occurrence.event.night {
+                    "tonight"
+                } else {
+                    "tomorrow"
+                }
+            ),
+            body: occurrence.hijri.to_string(),
+        })
+        .collect()
+}
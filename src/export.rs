@@ -0,0 +1,309 @@
+//! Timetables of a range of days written out as CSV or JSON, for noticeboards
+//! and spreadsheets, as iCalendar for calendar apps, or laid out for printing.
+
+use std::{
+    fmt::Write as _,
+    path::{Path, PathBuf},
+};
+
+use chrono::{DateTime, Datelike, Local, NaiveDate, Utc};
+use druid::Data;
+use serde::Serialize;
+
+use crate::{
+    app_state::AppState,
+    config::{remote_defs, AsrConfig, AvailableMethods, HijriConfig},
+    hijri::HijriDate,
+    ical, printable,
+    timetable::{self, Day},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Csv,
+    Json,
+    Ics,
+    Html,
+    Pdf,
+}
+
+impl Format {
+    pub fn extension(self) -> &'static str {
+        match self {
+            Format::Csv => "csv",
+            Format::Json => "json",
+            Format::Ics => "ics",
+            Format::Html => "html",
+            Format::Pdf => "pdf",
+        }
+    }
+
+    pub fn of(path: &Path) -> Option<Format> {
+        match path.extension()?.to_str()?.to_ascii_lowercase().as_str() {
+            "csv" => Some(Format::Csv),
+            "json" => Some(Format::Json),
+            "ics" => Some(Format::Ics),
+            "html" | "htm" => Some(Format::Html),
+            "pdf" => Some(Format::Pdf),
+            _ => None,
+        }
+    }
+}
+
+/// The days to export, as chosen in the timetable window.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Data)]
+pub enum Span {
+    /// The month shown in the timetable.
+    Month,
+    /// The year of the month shown in the timetable.
+    Year,
+    Custom,
+}
+
+/// The config the times were calculated with.
+#[derive(Debug, Serialize)]
+struct Metadata<'a> {
+    generated: DateTime<Local>,
+    from: NaiveDate,
+    to: NaiveDate,
+    location: Option<&'a str>,
+    latitude: f64,
+    longitude: f64,
+    method: AvailableMethods,
+    /// Minutes after sunrise and before zawal.
+    ishraq: Option<(u8, u8)>,
+    asr: AsrConfig,
+    hijri: HijriConfig,
+    #[serde(with = "remote_defs::TimeAdjustment")]
+    user_adjustments: adhaan::TimeAdjustment,
+}
+
+#[derive(Debug, Serialize)]
+struct Row {
+    date: NaiveDate,
+    hijri: String,
+    fajr: DateTime<Local>,
+    sunrise: DateTime<Local>,
+    ishraq_start: Option<DateTime<Local>>,
+    ishraq_end: Option<DateTime<Local>>,
+    dhuhr: DateTime<Local>,
+    asr_awwal: DateTime<Local>,
+    asr_thaani: DateTime<Local>,
+    maghrib: DateTime<Local>,
+    isha: DateTime<Local>,
+    qiyam: DateTime<Local>,
+}
+
+impl From<&Day> for Row {
+    fn from(day: &Day) -> Row {
+        let local = |time: DateTime<Utc>| time.with_timezone(&Local);
+        Row {
+            date: day.date,
+            hijri: format_hijri(day.hijri),
+            fajr: local(day.fajr),
+            sunrise: local(day.sunrise),
+            ishraq_start: day.ishraq.map(|(start, _)| local(start)),
+            ishraq_end: day.ishraq.map(|(_, end)| local(end)),
+            dhuhr: local(day.dhuhr),
+            asr_awwal: local(day.asr_awwal),
+            asr_thaani: local(day.asr_thaani),
+            maghrib: local(day.maghrib),
+            isha: local(day.isha),
+            qiyam: local(day.qiyam),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct Timetable<'a> {
+    metadata: Metadata<'a>,
+    days: Vec<Row>,
+}
+
+const CSV_COLUMNS: &str =
+    "date,hijri,fajr,sunrise,ishraq_start,ishraq_end,dhuhr,asr_awwal,asr_thaani,maghrib,isha,qiyam";
+
+fn format_time(time: DateTime<Local>) -> String {
+    time.format("%H:%M").to_string()
+}
+
+fn format_hijri(date: HijriDate) -> String {
+    format!("{}-{:02}-{:02}", date.year, date.month, date.day)
+}
+
+/// The first and last days of the span chosen in the timetable window.
+pub fn range_of(data: &AppState) -> anyhow::Result<(NaiveDate, NaiveDate)> {
+    let month = data.timetable_month;
+    Ok(match data.export_span {
+        Span::Month => timetable::month_bounds(month),
+        Span::Year => (
+            NaiveDate::from_ymd(month.year(), 1, 1),
+            NaiveDate::from_ymd(month.year(), 12, 31),
+        ),
+        Span::Custom => {
+            let from: NaiveDate = data.export_from.trim().parse()?;
+            let to: NaiveDate = data.export_to.trim().parse()?;
+            anyhow::ensure!(from <= to, "The range ends before it starts");
+            (from, to)
+        }
+    })
+}
+
+/// Writes the timetable from `from` to `to` to `path`.
+pub fn write(
+    data: &AppState,
+    from: NaiveDate,
+    to: NaiveDate,
+    format: Format,
+    path: &Path,
+) -> anyhow::Result<()> {
+    let contents = match format {
+        Format::Csv => csv(data, from, to)?,
+        Format::Json => json(data, from, to)?,
+        Format::Ics => ical::calendar(data, from, to, data.config.calendar),
+        Format::Html => printable::html(data, from, to)?,
+        Format::Pdf => return printable::write_pdf(data, from, to, path),
+    };
+    std::fs::write(path, contents)?;
+    Ok(())
+}
+
+fn metadata(data: &AppState, from: NaiveDate, to: NaiveDate) -> Metadata {
+    let config = &data.config;
+    Metadata {
+        generated: Local::now(),
+        from,
+        to,
+        location: config.location_name.as_deref(),
+        latitude: config.coordinates.latitude,
+        longitude: config.coordinates.longitude,
+        method: config.method,
+        ishraq: config.ishraq,
+        asr: config.asr,
+        hijri: config.hijri,
+        user_adjustments: config.user_adjustments,
+    }
+}
+
+fn rows(data: &AppState, from: NaiveDate, to: NaiveDate) -> Vec<Row> {
+    timetable::days(data, from, to)
+        .iter()
+        .map(Row::from)
+        .collect()
+}
+
+fn csv(data: &AppState, from: NaiveDate, to: NaiveDate) -> anyhow::Result<String> {
+    let mut csv = String::new();
+    for line in toml::to_string(&metadata(data, from, to))?.lines() {
+        writeln!(csv, "# {}", line)?;
+    }
+    writeln!(csv, "{}", CSV_COLUMNS)?;
+    let window = |time: Option<_>| time.map(format_time).unwrap_or_default();
+    for row in rows(data, from, to) {
+        writeln!(
+            csv,
+            "{},{},{},{},{},{},{},{},{},{},{},{}",
+            row.date,
+            row.hijri,
+            format_time(row.fajr),
+            format_time(row.sunrise),
+            window(row.ishraq_start),
+            window(row.ishraq_end),
+            format_time(row.dhuhr),
+            format_time(row.asr_awwal),
+            format_time(row.asr_thaani),
+            format_time(row.maghrib),
+            format_time(row.isha),
+            format_time(row.qiyam),
+        )?;
+    }
+    Ok(csv)
+}
+
+fn json(data: &AppState, from: NaiveDate, to: NaiveDate) -> anyhow::Result<String> {
+    Ok(serde_json::to_string_pretty(&Timetable {
+        metadata: metadata(data, from, to),
+        days: rows(data, from, to),
+    })?)
+}
+
+/// Exports the range chosen in the timetable window to the exports folder,
+/// returning the file written.
+pub fn write_chosen(data: &AppState, format: Format) -> anyhow::Result<PathBuf> {
+    let (from, to) = range_of(data)?;
+    let mut path = crate::utils::exports_dir();
+    std::fs::create_dir_all(&path)?;
+    path.push(format!("adhaan-{}-{}.{}", from, to, format.extension()));
+    write(data, from, to, format, &path)?;
+    Ok(path)
+}
+
+/// An export asked for on the command line.
+pub struct Request {
+    pub path: PathBuf,
+    pub format: Format,
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+}
+
+const USAGE: &str = "Usage: adhaan-gui --export FILE.csv|FILE.json|FILE.ics|FILE.html|FILE.pdf \
+    [--month YYYY-MM | --year YYYY | --from YYYY-MM-DD --to YYYY-MM-DD]";
+
+/// Reads `--export` and its range from the command line arguments, defaulting
+/// to the month of `today`.
+pub fn parse_args(
+    mut args: impl Iterator<Item = String>,
+    today: NaiveDate,
+) -> anyhow::Result<Option<Request>> {
+    let mut path = None;
+    let mut month = None;
+    let mut year = None;
+    let mut from = None;
+    let mut to = None;
+
+    while let Some(arg) = args.next() {
+        let mut value = || {
+            args.next()
+                .ok_or_else(|| anyhow::format_err!("`{}` needs a value\n{}", arg, USAGE))
+        };
+        match arg.as_str() {
+            "--export" => path = Some(PathBuf::from(value()?)),
+            "--month" => month = Some(NaiveDate::parse_from_str(&(value()? + "-01"), "%Y-%m-%d")?),
+            "--year" => year = Some(value()?.parse::<i32>()?),
+            "--from" => from = Some(value()?.parse::<NaiveDate>()?),
+            "--to" => to = Some(value()?.parse::<NaiveDate>()?),
+            _ => anyhow::bail!("Unknown argument `{}`\n{}", arg, USAGE),
+        }
+    }
+
+    let path = match path {
+        Some(path) => path,
+        None if month.is_some() || year.is_some() || from.is_some() || to.is_some() => {
+            anyhow::bail!("The range needs `--export`\n{}", USAGE)
+        }
+        None => return Ok(None),
+    };
+    let format = Format::of(&path).ok_or_else(|| {
+        anyhow::format_err!(
+            "Export to a .csv, .json, .ics, .html or .pdf file\n{}",
+            USAGE
+        )
+    })?;
+
+    let (from, to) = match (month, year, from, to) {
+        (None, None, None, None) => timetable::month_bounds(today),
+        (Some(month), None, None, None) => timetable::month_bounds(month),
+        (None, Some(year), None, None) => NaiveDate::from_ymd_opt(year, 1, 1)
+            .zip(NaiveDate::from_ymd_opt(year, 12, 31))
+            .ok_or_else(|| anyhow::format_err!("Year {} is out of range", year))?,
+        (None, None, Some(from), Some(to)) if from <= to => (from, to),
+        _ => anyhow::bail!("Give one valid range\n{}", USAGE),
+    };
+
+    Ok(Some(Request {
+        path,
+        format,
+        from,
+        to,
+    }))
+}
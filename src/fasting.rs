@@ -0,0 +1,99 @@
+//! Recommended (sunnah) fasts.
+
+use adhaan::Prayer;
+use chrono::{Datelike, Duration, NaiveDate, Weekday};
+
+use crate::{
+    alerts::{Alert, AlertKind},
+    app_state::AppState,
+    config::Config,
+    hijri::HijriDate,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Fast {
+    Monday,
+    Thursday,
+    AyyamAlBid,
+    Arafah,
+    Tasua,
+    Ashura,
+    SixOfShawwal,
+}
+
+impl Fast {
+    pub fn label(self) -> &'static str {
+        match self {
+            Fast::Monday => "Monday",
+            Fast::Thursday => "Thursday",
+            Fast::AyyamAlBid => "Ayyam al-Bid",
+            Fast::Arafah => "Day of Arafah",
+            Fast::Tasua => "Tasu'a",
+            Fast::Ashura => "Ashura",
+            Fast::SixOfShawwal => "Six days of Shawwal",
+        }
+    }
+}
+
+/// Days on which fasting is not permitted: the two Eids and the days of Tashriq.
+fn is_forbidden(hijri: HijriDate) -> bool {
+    matches!((hijri.month, hijri.day), (10, 1) | (12, 10..=13))
+}
+
+/// The recommended fasts enabled in `config` falling on the civil date `date`.
+pub fn recommended_on(date: NaiveDate, config: &Config) -> Vec<Fast> {
+    let hijri = HijriDate::from_gregorian(
+        date + Duration::days(config.hijri.offset as _),
+        config.hijri.calendar,
+    );
+    // Ramadan is fasted anyway.
+    if is_forbidden(hijri) || hijri.month == 9 {
+        return vec![];
+    }
+
+    let fasting = &config.fasting;
+    let mut fasts = vec![];
+
+    match date.weekday() {
+        Weekday::Mon if fasting.mondays_thursdays => fasts.push(Fast::Monday),
+        Weekday::Thu if fasting.mondays_thursdays => fasts.push(Fast::Thursday),
+        _ => {}
+    }
+    match (hijri.month, hijri.day) {
+        (_, 13..=15) if fasting.ayyam_al_bid => fasts.push(Fast::AyyamAlBid),
+        (12, 9) if fasting.arafah => fasts.push(Fast::Arafah),
+        (1, 9) if fasting.ashura => fasts.push(Fast::Tasua),
+        (1, 10) if fasting.ashura => fasts.push(Fast::Ashura),
+        (10, 2..=7) if fasting.six_of_shawwal => fasts.push(Fast::SixOfShawwal),
+        _ => {}
+    }
+
+    fasts
+}
+
+/// Reminders after Isha for the fasts of tomorrow.
+pub fn alerts(data: &AppState) -> Vec<Alert> {
+    let isha = data.adjusted_time_of(Prayer::Isha);
+    let tomorrow = isha
+        .with_timezone(&chrono::Local)
+        .date()
+        .naive_local()
+        .succ();
+
+    let fasts = recommended_on(tomorrow, &data.config);
+    if fasts.is_empty() {
+        return vec![];
+    }
+
+    vec![Alert {
+        key: format!("fast/{}", tomorrow),
+        kind: AlertKind::Reminder,
+        at: isha + Duration::minutes(data.config.fasting.after_isha as _),
+        summary: "Sunnah fast tomorrow".into(),
+        body: fasts
+            .iter()
+            .map(|fast| fast.label())
+            .collect::<Vec<_>>()
+            .join(", "),
+    }]
+}
@@ -0,0 +1,186 @@
+//! A rolling prayer calendar served on localhost for calendar apps to
+//! subscribe to, at `http://localhost:<port>/prayers.ics`.
+
+use std::{
+    io::{BufRead, BufReader, Write},
+    net::{Ipv4Addr, TcpListener, TcpStream},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+};
+
+use chrono::{Duration, Local, NaiveDate};
+use druid::{widget::prelude::*, Selector};
+
+use crate::{app_state::AppState, config::Config, ical};
+
+/// Days the feed covers, from today.
+const DAYS: i64 = 60;
+const PATH: &str = "/prayers.ics";
+
+/// The config to calculate the feed with, and the port it is served on.
+static SERVED: Mutex<Option<(Config, u16)>> = Mutex::new(None);
+/// Bumped for every server started, so older ones know to stop.
+static GENERATION: AtomicU64 = AtomicU64::new(0);
+
+/// Sent when the settings are saved, to serve the feed as they ask.
+pub const APPLY: Selector = Selector::new("adhaan.feed.apply");
+
+/// Serves the feed as `config` asks, starting, moving or stopping the server.
+pub fn update(config: &Config) {
+    let mut served = SERVED.lock().unwrap();
+    let old_port = served.as_ref().map(|(_, port)| *port);
+    let new_port = match config.calendar.feed {
+        Some(0) => {
+            tracing::error!("Not serving the calendar feed on port 0");
+            None
+        }
+        port => port,
+    };
+    *served = new_port.map(|port| (config.clone(), port));
+    drop(served);
+
+    if old_port == new_port {
+        return;
+    }
+    let generation = GENERATION.fetch_add(1, Ordering::SeqCst) + 1;
+    if let Some(port) = old_port {
+        // Wakes the old server up from waiting for a connection, to stop.
+        let _ = TcpStream::connect((Ipv4Addr::LOCALHOST, port));
+    }
+    if let Some(port) = new_port {
+        match TcpListener::bind((Ipv4Addr::LOCALHOST, port)) {
+            Ok(listener) => {
+                tracing::info!("Serving the calendar feed on port {}", port);
+                std::thread::spawn(move || serve(listener, generation));
+            }
+            Err(e) => {
+                tracing::error!("Failed to serve the calendar feed on {}: {:?}", port, e);
+                // Tried again on the next change of config.
+                *SERVED.lock().unwrap() = None;
+            }
+        }
+    }
+}
+
+fn serve(listener: TcpListener, generation: u64) {
+    // The calendar last served, with what it was made from.
+    let mut cache: Option<(Config, NaiveDate, Arc<String>)> = None;
+
+    for stream in listener.incoming() {
+        if GENERATION.load(Ordering::SeqCst) != generation {
+            return;
+        }
+        let stream = match stream {
+            Ok(stream) => stream,
+            Err(e) => {
+                tracing::error!("Failed to accept a feed connection: {:?}", e);
+                continue;
+            }
+        };
+
+        let config = match &*SERVED.lock().unwrap() {
+            Some((config, _)) => config.clone(),
+            None => return,
+        };
+        let today = Local::today().naive_local();
+        let calendar = match &cache {
+            Some((cached_config, date, calendar)) if *cached_config == config && *date == today => {
+                calendar.clone()
+            }
+            _ => {
+                let calendar = Arc::new(calendar(&config, today));
+                cache = Some((config, today, calendar.clone()));
+                calendar
+            }
+        };
+
+        if let Err(e) = respond(stream, &calendar) {
+            tracing::error!("Failed to serve the calendar feed: {:?}", e);
+        }
+    }
+}
+
+fn calendar(config: &Config, today: NaiveDate) -> String {
+    let data = AppState::new(config.clone(), today, Arc::default());
+    ical::calendar(
+        &data,
+        today,
+        today + Duration::days(DAYS - 1),
+        config.calendar,
+    )
+}
+
+fn respond(mut stream: TcpStream, calendar: &str) -> std::io::Result<()> {
+    // A stalled client would hold up every other one.
+    stream.set_read_timeout(Some(std::time::Duration::from_secs(5)))?;
+    let mut reader = BufReader::new(&stream);
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    // The headers are read through, as replying before the client is done
+    // sending may reset the connection.
+    loop {
+        let mut header = String::new();
+        if reader.read_line(&mut header)? == 0 || header == "\r\n" || header == "\n" {
+            break;
+        }
+    }
+    let mut parts = request_line.split_whitespace();
+    let (method, path) = (parts.next(), parts.next());
+    // Calendar apps may add a query to bust caches.
+    let path = path.map(|path| path.split('?').next().unwrap());
+
+    let (status, body) = match (method, path) {
+        (Some("GET" | "HEAD"), Some(PATH)) => ("200 OK", calendar),
+        (Some("GET" | "HEAD"), _) => ("404 Not Found", ""),
+        _ => ("405 Method Not Allowed", ""),
+    };
+    write!(
+        stream,
+        "HTTP/1.1 {}\r\n\
+         Content-Type: text/calendar; charset=utf-8\r\n\
+         Content-Length: {}\r\n\
+         Cache-Control: no-cache\r\n\
+         Connection: close\r\n\r\n",
+        status,
+        body.len()
+    )?;
+    if method != Some("HEAD") {
+        stream.write_all(body.as_bytes())?;
+    }
+    stream.flush()
+}
+
+/// Keeps the feed in step with the config, as it is saved.
+pub struct FeedController;
+
+impl<W: Widget<AppState>> druid::widget::Controller<AppState, W> for FeedController {
+    fn event(
+        &mut self,
+        child: &mut W,
+        ctx: &mut EventCtx,
+        event: &Event,
+        data: &mut AppState,
+        env: &Env,
+    ) {
+        match event {
+            Event::Command(command) if command.is(APPLY) => update(&data.config),
+            _ => child.event(ctx, event, data, env),
+        }
+    }
+
+    fn lifecycle(
+        &mut self,
+        child: &mut W,
+        ctx: &mut LifeCycleCtx,
+        event: &LifeCycle,
+        data: &AppState,
+        env: &Env,
+    ) {
+        if let LifeCycle::WidgetAdded = event {
+            update(&data.config);
+        }
+        child.lifecycle(ctx, event, data, env)
+    }
+}
@@ -0,0 +1,243 @@
+//! Conversions between the Gregorian and the Hijri calendars.
+
+use std::{cell::RefCell, collections::HashMap, fmt};
+
+use chrono::{Datelike, Duration, NaiveDate};
+use druid::Data;
+use serde::{Deserialize, Serialize};
+
+use crate::{astro, config::KAABA_COORDINATES};
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Hash, Clone, Copy, Data)]
+pub enum HijriCalendar {
+    /// The criterion of the Umm al-Qura calendar as used since 1423 AH: the month
+    /// begins on the evening of the 29th if the conjunction happened before
+    /// sunset and the moon sets after the sun in Makkah.
+    UmmAlQura,
+    /// The arithmetical calendar with the common 2, 5, 7, 10, 13, 16, 18, 21, 24,
+    /// 26, 29 leap years of a 30 year cycle.
+    Tabular,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct HijriDate {
+    pub year: i32,
+    pub month: u8,
+    pub day: u8,
+}
+
+pub const MONTH_NAMES: [&str; 12] = [
+    "Muharram",
+    "Safar",
+    "Rabi' al-Awwal",
+    "Rabi' al-Thani",
+    "Jumada al-Ula",
+    "Jumada al-Akhirah",
+    "Rajab",
+    "Sha'ban",
+    "Ramadan",
+    "Shawwal",
+    "Dhu al-Qa'dah",
+    "Dhu al-Hijjah",
+];
+
+/// Days from the common era of 1 Muharram 1 AH, per the civil epoch.
+const EPOCH_DAYS_FROM_CE: i32 = 227_015;
+/// Month index of 1 Muharram 1356 AH, where the Umm al-Qura tables begin. Months
+/// are reckoned forwards from it, which keeps them in step with the moon.
+const UMM_AL_QURA_EPOCH_INDEX: i32 = 1355 * 12;
+
+fn umm_al_qura_epoch() -> NaiveDate {
+    NaiveDate::from_ymd(1937, 3, 14)
+}
+
+/// First day of the month counted as `index` from 1 Muharram 1 AH.
+fn month_start(index: i32, calendar: HijriCalendar) -> NaiveDate {
+    thread_local! {
+        static MONTH_STARTS: RefCell<HashMap<(i32, HijriCalendar), NaiveDate>> =
+            RefCell::new(HashMap::new());
+    }
+
+    MONTH_STARTS.with(|starts| {
+        let mut starts = starts.borrow_mut();
+        if let Some(start) = starts.get(&(index, calendar)) {
+            return *start;
+        }
+
+        let start = match calendar {
+            HijriCalendar::Tabular => tabular_month_start(index),
+            HijriCalendar::UmmAlQura => {
+                // Every month is found from its neighbour towards the epoch, so
+                // that each lasts 29 or 30 days.
+                let step = if index > UMM_AL_QURA_EPOCH_INDEX {
+                    -1
+                } else {
+                    1
+                };
+                let mut known = index;
+                while known != UMM_AL_QURA_EPOCH_INDEX && !starts.contains_key(&(known, calendar)) {
+                    known += step;
+                }
+                let mut start = starts
+                    .get(&(known, calendar))
+                    .copied()
+                    .unwrap_or_else(umm_al_qura_epoch);
+                while known != index {
+                    known -= step;
+                    start = if step < 0 {
+                        umm_al_qura_month_after(start)
+                    } else {
+                        umm_al_qura_month_before(start)
+                    };
+                    starts.insert((known, calendar), start);
+                }
+                start
+            }
+        };
+        starts.insert((index, calendar), start);
+        start
+    })
+}
+
+fn tabular_month_start(index: i32) -> NaiveDate {
+    let (year, month) = (index.div_euclid(12) + 1, index.rem_euclid(12));
+    NaiveDate::from_num_days_from_ce(
+        EPOCH_DAYS_FROM_CE
+            + (year - 1) * 354
+            + (3 + 11 * year).div_euclid(30)
+            + (59 * month + 1) / 2,
+    )
+}
+
+/// Whether the month ends on the evening of `date` by the Umm al-Qura criterion.
+fn umm_al_qura_crescent(date: NaiveDate) -> bool {
+    astro::sunset(date, KAABA_COORDINATES).map_or(false, |sunset| {
+        // The conjunction of the month ending, not of the one before it.
+        let conjunction = astro::new_moon(astro::lunation_at(sunset));
+        sunset - conjunction < Duration::days(15)
+            && astro::moon_horizontal(sunset, KAABA_COORDINATES).altitude > astro::HORIZON
+    })
+}
+
+/// Start of the month after the one starting on `start`, checked on its 29th.
+fn umm_al_qura_month_after(start: NaiveDate) -> NaiveDate {
+    let day_29 = start + Duration::days(28);
+    day_29 + Duration::days(if umm_al_qura_crescent(day_29) { 1 } else { 2 })
+}
+
+/// Start of the month before the one starting on `next`.
+fn umm_al_qura_month_before(next: NaiveDate) -> NaiveDate {
+    next - Duration::days(if umm_al_qura_crescent(next.pred()) {
+        29
+    } else {
+        30
+    })
+}
+
+fn month_index_of(date: NaiveDate, calendar: HijriCalendar) -> i32 {
+    let mut index = ((date.num_days_from_ce() - EPOCH_DAYS_FROM_CE) as f64
+        / astro::MEAN_SYNODIC_MONTH)
+        .floor() as i32;
+    while month_start(index, calendar) > date {
+        index -= 1;
+    }
+    while month_start(index + 1, calendar) <= date {
+        index += 1;
+    }
+    index
+}
+
+impl HijriDate {
+    pub fn from_gregorian(date: NaiveDate, calendar: HijriCalendar) -> HijriDate {
+        let index = month_index_of(date, calendar);
+        HijriDate {
+            year: index.div_euclid(12) + 1,
+            month: (index.rem_euclid(12) + 1) as _,
+            day: ((date - month_start(index, calendar)).num_days() + 1) as _,
+        }
+    }
+
+    pub fn to_gregorian(self, calendar: HijriCalendar) -> NaiveDate {
+        month_start(self.month_index(), calendar) + Duration::days(self.day as i64 - 1)
+    }
+
+    pub fn days_in_month(year: i32, month: u8, calendar: HijriCalendar) -> u8 {
+        let index = (year - 1) * 12 + month as i32 - 1;
+        (month_start(index + 1, calendar) - month_start(index, calendar)).num_days() as _
+    }
+
+    pub fn month_name(self) -> &'static str {
+        MONTH_NAMES[self.month as usize - 1]
+    }
+
+    fn month_index(self) -> i32 {
+        (self.year - 1) * 12 + self.month as i32 - 1
+    }
+}
+
+impl fmt::Display for HijriDate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {} {} AH", self.day, self.month_name(), self.year)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hijri(year: i32, month: u8, day: u8) -> HijriDate {
+        HijriDate { year, month, day }
+    }
+
+    #[test]
+    fn umm_al_qura_month_starts() {
+        for (gregorian, expected) in [
+            (NaiveDate::from_ymd(2023, 3, 23), hijri(1444, 9, 1)),
+            (NaiveDate::from_ymd(2023, 7, 19), hijri(1445, 1, 1)),
+            (NaiveDate::from_ymd(2024, 3, 11), hijri(1445, 9, 1)),
+            (NaiveDate::from_ymd(2025, 3, 30), hijri(1446, 10, 1)),
+            (NaiveDate::from_ymd(2025, 6, 26), hijri(1447, 1, 1)),
+            (NaiveDate::from_ymd(2026, 2, 18), hijri(1447, 9, 1)),
+        ] {
+            assert_eq!(
+                HijriDate::from_gregorian(gregorian, HijriCalendar::UmmAlQura),
+                expected
+            );
+            assert_eq!(expected.to_gregorian(HijriCalendar::UmmAlQura), gregorian);
+        }
+    }
+
+    #[test]
+    fn tabular_dates() {
+        for (gregorian, expected) in [
+            // 16 July 622 in the Julian calendar.
+            (NaiveDate::from_ymd(622, 7, 19), hijri(1, 1, 1)),
+            (NaiveDate::from_ymd(2023, 7, 19), hijri(1445, 1, 1)),
+            (NaiveDate::from_ymd(2024, 3, 11), hijri(1445, 9, 1)),
+            (NaiveDate::from_ymd(2025, 3, 30), hijri(1446, 9, 30)),
+            (NaiveDate::from_ymd(2025, 6, 26), hijri(1446, 12, 29)),
+        ] {
+            assert_eq!(
+                HijriDate::from_gregorian(gregorian, HijriCalendar::Tabular),
+                expected
+            );
+            assert_eq!(expected.to_gregorian(HijriCalendar::Tabular), gregorian);
+        }
+    }
+
+    #[test]
+    fn round_trip() {
+        for calendar in [HijriCalendar::UmmAlQura, HijriCalendar::Tabular] {
+            let mut date = NaiveDate::from_ymd(2023, 1, 1);
+            while date < NaiveDate::from_ymd(2027, 1, 1) {
+                let converted = HijriDate::from_gregorian(date, calendar);
+                assert!(
+                    converted.day
+                        <= HijriDate::days_in_month(converted.year, converted.month, calendar)
+                );
+                assert_eq!(converted.to_gregorian(calendar), date, "{:?}", calendar);
+                date = date.succ();
+            }
+        }
+    }
+}
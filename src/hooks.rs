@@ -0,0 +1,186 @@
+//! User commands run and webhooks posted on prayer events, as declared under
+//! `[hooks]` and `[webhooks]` in config.toml.
+
+use std::process::Command;
+
+use chrono::Duration;
+
+use crate::{
+    alerts::{waqt_key, Alert, AlertKind},
+    app_state::{AppState, Waqt},
+    webhooks::{self, Payload},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HookEvent {
+    WaqtStart,
+    PreReminder(u16),
+    Critical,
+    WaqtEnd,
+    DayRollover,
+}
+
+impl HookEvent {
+    pub fn name(self) -> &'static str {
+        match self {
+            HookEvent::WaqtStart => "waqt_start",
+            HookEvent::PreReminder(_) => "pre_reminder",
+            HookEvent::Critical => "critical",
+            HookEvent::WaqtEnd => "waqt_end",
+            HookEvent::DayRollover => "day_rollover",
+        }
+    }
+}
+
+pub fn alerts(data: &AppState, waqts: &[Waqt]) -> Vec<Alert> {
+    let config = &data.config;
+    let mut alerts = vec![];
+    for &waqt in waqts {
+        let mut push = |event: HookEvent, at| {
+            if !config.hooks.get(event).is_empty() || config.webhooks.is_subscribed(event) {
+                let key = waqt_key(&format!("hook/{}", event.name()), &waqt);
+                alerts.push(Alert {
+                    key: match event {
+                        HookEvent::PreReminder(minutes) => format!("{}/{}", key, minutes),
+                        _ => key,
+                    },
+                    kind: AlertKind::Hook(event, waqt),
+                    at,
+                    summary: String::new(),
+                    body: String::new(),
+                })
+            }
+        };
+
+        push(HookEvent::WaqtStart, waqt.start);
+        push(HookEvent::WaqtEnd, waqt.end);
+        if !data.is_waqt_prayed(&waqt) {
+            push(
+                HookEvent::Critical,
+                waqt.end - Duration::minutes(data.config.critical_at as _),
+            );
+        }
+        let pre_reminders = match &config.hooks.pre_reminder_minutes {
+            Some(minutes) => minutes,
+            None => &config.notifications.before_start.get(waqt.prayer).0,
+        };
+        for &minutes in pre_reminders {
+            push(
+                HookEvent::PreReminder(minutes),
+                waqt.start - Duration::minutes(minutes as _),
+            );
+        }
+    }
+    alerts
+}
+
+/// Runs the hooks of an event about `waqt`.
+pub fn run_for_waqt(data: &AppState, event: HookEvent, waqt: Waqt) {
+    let mut vars = common_vars(data, event);
+    vars.push(("ADHAAN_PRAYER", format!("{:?}", waqt.prayer)));
+    vars.push(("ADHAAN_LABEL", waqt.label.into()));
+    vars.push(("ADHAAN_START", waqt.start.to_rfc3339()));
+    vars.push(("ADHAAN_END", waqt.end.to_rfc3339()));
+    if let HookEvent::PreReminder(minutes) = event {
+        vars.push(("ADHAAN_MINUTES_BEFORE", minutes.to_string()));
+    }
+    run(data.config.hooks.get(event), &vars);
+
+    webhooks::post(
+        &data.config.webhooks,
+        event,
+        Payload {
+            prayer: Some(format!("{:?}", waqt.prayer)),
+            label: Some(waqt.label),
+            start: Some(waqt.start),
+            end: Some(waqt.end),
+            minutes_before: match event {
+                HookEvent::PreReminder(minutes) => Some(minutes),
+                _ => None,
+            },
+            ..payload(data, event)
+        },
+    );
+}
+
+/// Runs the hooks of the start of a new day, after `data` is recalculated for it.
+pub fn run_day_rollover(data: &AppState) {
+    let mut vars = common_vars(data, HookEvent::DayRollover);
+    vars.push(("ADHAAN_DATE", data.date.to_string()));
+    run(data.config.hooks.get(HookEvent::DayRollover), &vars);
+
+    webhooks::post(
+        &data.config.webhooks,
+        HookEvent::DayRollover,
+        Payload {
+            date: Some(data.date),
+            ..payload(data, HookEvent::DayRollover)
+        },
+    );
+}
+
+fn payload(data: &AppState, event: HookEvent) -> Payload {
+    Payload {
+        event: event.name(),
+        prayer: None,
+        label: None,
+        start: None,
+        end: None,
+        minutes_before: None,
+        date: None,
+        location: data.config.location_name.clone(),
+        latitude: data.config.coordinates.latitude,
+        longitude: data.config.coordinates.longitude,
+    }
+}
+
+fn common_vars(data: &AppState, event: HookEvent) -> Vec<(&'static str, String)> {
+    vec![
+        ("ADHAAN_EVENT", event.name().into()),
+        (
+            "ADHAAN_LOCATION",
+            data.config.location_name.clone().unwrap_or_default(),
+        ),
+        (
+            "ADHAAN_LATITUDE",
+            data.config.coordinates.latitude.to_string(),
+        ),
+        (
+            "ADHAAN_LONGITUDE",
+            data.config.coordinates.longitude.to_string(),
+        ),
+    ]
+}
+
+fn run(commands: &[String], vars: &[(&'static str, String)]) {
+    for command in commands {
+        tracing::debug!("Running hook `{}`", command);
+        match shell(command).envs(vars.iter().cloned()).spawn() {
+            // Reap it without holding up the UI.
+            Ok(mut child) => drop(std::thread::spawn(move || child.wait())),
+            Err(e) => tracing::error!("Failed to run hook `{}`: {:?}", command, e),
+        }
+    }
+}
+
+#[cfg(windows)]
+fn shell(command: &str) -> Command {
+    use std::os::windows::process::CommandExt;
+
+    /// Keeps a console window from flashing up.
+    const CREATE_NO_WINDOW: u32 = 0x08000000;
+
+    let mut shell = Command::new("cmd");
+    shell
+        .arg("/C")
+        .arg(command)
+        .creation_flags(CREATE_NO_WINDOW);
+    shell
+}
+
+#[cfg(not(windows))]
+fn shell(command: &str) -> Command {
+    let mut shell = Command::new("sh");
+    shell.arg("-c").arg(command);
+    shell
+}
@@ -0,0 +1,172 @@
+//! Prayer times as iCalendar (RFC 5545) events, for overlaying on other
+//! calendars.
+
+use adhaan::Prayer;
+use chrono::{DateTime, Duration, Local, NaiveDate, NaiveDateTime, Offset, TimeZone, Utc};
+
+use crate::{app_state::AppState, config::CalendarConfig, utils};
+
+const PRODUCT_ID: &str = "-//Adhaan//AdhaanGUI//EN";
+/// Used when the system time zone has no IANA name.
+const FALLBACK_TZID: &str = "Adhaan-Local";
+
+/// A calendar of every waqt from `from` to `to`, both included.
+pub fn calendar(data: &AppState, from: NaiveDate, to: NaiveDate, config: CalendarConfig) -> String {
+    let tzid = iana_time_zone::get_timezone().unwrap_or_else(|e| {
+        tracing::error!("Failed to get the time zone name: {:?}", e);
+        FALLBACK_TZID.into()
+    });
+    let stamp = Utc::now().format("%Y%m%dT%H%M%SZ").to_string();
+
+    let mut lines = vec![
+        "BEGIN:VCALENDAR".to_string(),
+        "VERSION:2.0".into(),
+        format!("PRODID:{}", PRODUCT_ID),
+        "CALSCALE:GREGORIAN".into(),
+        "METHOD:PUBLISH".into(),
+        format!(
+            "X-WR-CALNAME:{}",
+            escape(&match &data.config.location_name {
+                Some(location) => format!("Prayer times in {}", location),
+                None => "Prayer times".into(),
+            })
+        ),
+        format!("X-WR-TIMEZONE:{}", tzid),
+    ];
+    lines.extend(time_zone(&tzid, from, to));
+
+    let mut date = from;
+    while date <= to {
+        let day = data.on_date(date);
+        for waqt in day.waqts() {
+            lines.push("BEGIN:VEVENT".into());
+            lines.push(format!("UID:{}-{}@adhaan", date, uid_name(waqt.prayer)));
+            lines.push(format!("DTSTAMP:{}", stamp));
+            lines.push(format!("LAST-MODIFIED:{}", stamp));
+            lines.push(format!("DTSTART;TZID={}:{}", tzid, local(waqt.start)));
+            lines.push(format!("DTEND;TZID={}:{}", tzid, local(waqt.end)));
+            lines.push(format!("SUMMARY:{}", escape(waqt.label)));
+            if let Some(location) = &data.config.location_name {
+                lines.push(format!("LOCATION:{}", escape(location)));
+            }
+            lines.push("TRANSP:TRANSPARENT".into());
+            if let Some(minutes) = config.reminder {
+                lines.push("BEGIN:VALARM".into());
+                lines.push("ACTION:DISPLAY".into());
+                lines.push(format!("DESCRIPTION:{}", escape(waqt.label)));
+                lines.push(format!("TRIGGER:-PT{}M", minutes));
+                lines.push("END:VALARM".into());
+            }
+            lines.push("END:VEVENT".into());
+        }
+        date = date.succ();
+    }
+    lines.push("END:VCALENDAR".into());
+
+    let mut calendar = String::new();
+    for line in lines {
+        fold(&mut calendar, &line);
+    }
+    calendar
+}
+
+/// The name of a waqt in UIDs, the same whichever Asr starts it so that
+/// changing the Asr mode updates the events on re-import.
+fn uid_name(prayer: Prayer) -> String {
+    match prayer {
+        Prayer::AsrAwwal | Prayer::AsrThaani => "asr".into(),
+        prayer => format!("{:?}", prayer).to_lowercase(),
+    }
+}
+
+fn local(time: DateTime<Utc>) -> String {
+    format_local(time.with_timezone(&Local).naive_local())
+}
+
+fn format_local(time: NaiveDateTime) -> String {
+    time.format("%Y%m%dT%H%M%S").to_string()
+}
+
+fn format_offset(seconds: i32) -> String {
+    let sign = if seconds < 0 { '-' } else { '+' };
+    let minutes = seconds.abs() / 60;
+    format!("{}{:02}{:02}", sign, minutes / 60, minutes % 60)
+}
+
+fn offset_at(time: DateTime<Utc>) -> i32 {
+    Local
+        .offset_from_utc_datetime(&time.naive_utc())
+        .fix()
+        .local_minus_utc()
+}
+
+/// The VTIMEZONE of the system time zone, with an observance for each offset
+/// in effect from `from` to `to`, as the system reports them.
+fn time_zone(tzid: &str, from: NaiveDate, to: NaiveDate) -> Vec<String> {
+    // A day either side covers events running past midnight.
+    let start = utils::local_midnight(from.pred());
+    let end = utils::local_midnight(to.succ().succ());
+
+    // The offsets in effect, each with when it took effect.
+    let mut offsets = vec![(start, offset_at(start))];
+    let mut time = start;
+    while time < end {
+        let next = time + Duration::hours(1);
+        if offset_at(next) != offset_at(time) {
+            // Transitions happen on the minute.
+            let (mut before, mut after) = (time, next);
+            while after - before > Duration::minutes(1) {
+                let middle = before + (after - before) / 2;
+                if offset_at(middle) == offset_at(before) {
+                    before = middle;
+                } else {
+                    after = middle;
+                }
+            }
+            offsets.push((after, offset_at(after)));
+        }
+        time = next;
+    }
+
+    let standard = offsets.iter().map(|&(_, offset)| offset).min().unwrap();
+    let mut lines = vec!["BEGIN:VTIMEZONE".to_string(), format!("TZID:{}", tzid)];
+    let mut offset_from = offsets[0].1;
+    for &(since, offset) in &offsets {
+        let kind = if offset == standard {
+            "STANDARD"
+        } else {
+            "DAYLIGHT"
+        };
+        // Observances start at the local time under the offset before them.
+        let since = since.naive_utc() + Duration::seconds(offset_from as _);
+        lines.push(format!("BEGIN:{}", kind));
+        lines.push(format!("DTSTART:{}", format_local(since)));
+        lines.push(format!("TZOFFSETFROM:{}", format_offset(offset_from)));
+        lines.push(format!("TZOFFSETTO:{}", format_offset(offset)));
+        lines.push(format!("END:{}", kind));
+        offset_from = offset;
+    }
+    lines.push("END:VTIMEZONE".into());
+    lines
+}
+
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace('\n', "\\n")
+}
+
+/// Appends `line`, folded at 75 octets, without splitting characters.
+fn fold(calendar: &mut String, line: &str) {
+    let mut width = 0;
+    for c in line.chars() {
+        if width + c.len_utf8() > 75 {
+            calendar.push_str("\r\n ");
+            width = 1;
+        }
+        calendar.push(c);
+        width += c.len_utf8();
+    }
+    calendar.push_str("\r\n");
+}
@@ -2,71 +2,126 @@
 
 use adhaan::*;
 
+mod alarm;
+mod alerts;
 mod app_state;
+mod astro;
+mod audio;
 mod config;
+mod events;
+mod export;
+mod fasting;
+mod feed;
+mod hijri;
+mod hooks;
+mod ical;
+mod media;
+mod moon;
+mod notification;
+mod prayed;
+mod presence;
+mod printable;
+mod qibla;
+mod speech;
+mod timeline;
+mod timetable;
 mod ui_about;
+mod ui_alarm;
+mod ui_date_picker;
 mod ui_main;
+mod ui_moon;
+mod ui_qibla;
 mod ui_settings;
+mod ui_timetable;
 mod utils;
+mod webhooks;
 #[allow(unused)]
 mod widgets;
 
 use crate::{app_state::*, config::*};
 
-#[cfg(windows)]
 pub fn main() -> anyhow::Result<()> {
     let config = Config::load().unwrap();
 
     let main_window = druid::WindowDesc::new(ui_main::main_root())
         .title("Adhaan")
         .show_titlebar(false)
-        .show_in_taskbar(false)
+        // Elsewhere there is no tray icon to bring the window up from.
+        .show_in_taskbar(cfg!(not(windows)))
         .set_always_on_top(true)
         .resizable(false)
         .window_size(ui_main::size::MAIN_WINDOW)
         .set_level(druid::WindowLevel::AppWindow);
 
-    let initial_state = AppState {
-        prayers: PrayerTimes::calculate(
-            chrono::Local::today().naive_local(),
-            config.coordinates,
-            config.method.get_parameters(),
-        )
-        .unwrap(),
+    let user_events = events::load_user_events().unwrap_or_else(|e| {
+        tracing::error!("Failed to load user events: {:?}", e);
+        vec![]
+    });
+    let mut initial_state = AppState::new(
         config,
-    };
+        chrono::Local::today().naive_local(),
+        std::sync::Arc::new(user_events),
+    );
+    initial_state.prayed = prayed::load().unwrap_or_else(|e| {
+        tracing::error!("Failed to load prayed marks: {:?}", e);
+        vec![]
+    });
 
-    let (tray_event_tx, tay_event_rx) = std::sync::mpsc::channel::<()>();
+    // Without a console of its own, the app writes its errors and usage to the
+    // one it is run from.
+    #[cfg(windows)]
+    if std::env::args().len() > 1 {
+        unsafe { winapi::um::wincon::AttachConsole(winapi::um::wincon::ATTACH_PARENT_PROCESS) };
+    }
 
-    let _tray_icon = trayicon::TrayIconBuilder::new()
-        .tooltip("Adhaan")
-        .icon_from_buffer(ui_main::TRAY_ICON)
-        .on_click(())
-        .sender(tray_event_tx)
-        .build()
-        .map_err(|e| anyhow::format_err!("Tray error: {:?}", e))?;
+    if let Some(request) = export::parse_args(std::env::args().skip(1), initial_state.date)? {
+        return export::write(
+            &initial_state,
+            request.from,
+            request.to,
+            request.format,
+            &request.path,
+        );
+    }
+
+    notification::register();
+    initial_state.refresh_shown();
 
     let app_launcher = druid::AppLauncher::with_window(main_window)
         .configure_env(|env, app_state: &AppState| {
             app_state.config.apply_appearance_to_env(env);
         })
-        .delegate(ui_main::AppDelegate(None));
-
-    let ext_events_tray = app_launcher.get_external_handle();
-    std::thread::spawn(move || {
-        for _ in tay_event_rx {
-            ext_events_tray
-                .submit_command(ui_main::selector::SHOW, (), druid::Target::Auto)
-                .unwrap();
-        }
-    });
+        .delegate(ui_main::AppDelegate(None))
+        .localization_resources(
+            vec!["builtin.ftl".into(), "adhaan.ftl".into()],
+            utils::i18n_dir().to_string_lossy().into_owned(),
+        );
+
+    #[cfg(windows)]
+    let _tray_icon = {
+        let (tray_event_tx, tay_event_rx) = std::sync::mpsc::channel::<()>();
+
+        let tray_icon = trayicon::TrayIconBuilder::new()
+            .tooltip("Adhaan")
+            .icon_from_buffer(ui_main::TRAY_ICON)
+            .on_click(())
+            .sender(tray_event_tx)
+            .build()
+            .map_err(|e| anyhow::format_err!("Tray error: {:?}", e))?;
+
+        let ext_events_tray = app_launcher.get_external_handle();
+        std::thread::spawn(move || {
+            for _ in tay_event_rx {
+                ext_events_tray
+                    .submit_command(ui_main::selector::SHOW, (), druid::Target::Auto)
+                    .unwrap();
+            }
+        });
+
+        tray_icon
+    };
 
     app_launcher.log_to_console().launch(initial_state)?;
 
     Ok(())
 }
-
-#[cfg(not(windows))]
-pub fn main() {
-    eprintln!("Only available for windows now.")
-}
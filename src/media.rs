@@ -0,0 +1,229 @@
+//! Pausing of media players, through MPRIS on Linux and the system media
+//! session on Windows.
+
+use std::thread::JoinHandle;
+
+/// Players paused by `pause_all`, to be resumed later. Players are paused and
+/// resumed on threads of their own, as the calls to them block.
+#[derive(Debug, Default)]
+pub struct Paused(Vec<JoinHandle<Vec<String>>>);
+
+impl Paused {
+    pub fn extend(&mut self, other: Paused) {
+        self.0.extend(other.0);
+    }
+}
+
+pub fn pause_all() -> Paused {
+    Paused(vec![std::thread::spawn(|| {
+        platform::pause_all().unwrap_or_else(|e| {
+            tracing::error!("Failed to pause media: {:?}", e);
+            vec![]
+        })
+    })])
+}
+
+/// Resumes the players once they are paused.
+pub fn resume(paused: Paused) {
+    std::thread::spawn(move || {
+        let players = paused
+            .0
+            .into_iter()
+            .flat_map(|pausing| pausing.join().unwrap_or_default())
+            .collect();
+        if let Err(e) = platform::resume(players) {
+            tracing::error!("Failed to resume media: {:?}", e);
+        }
+    });
+}
+
+#[cfg(windows)]
+mod platform {
+    use windows::Media::Control::{
+        GlobalSystemMediaTransportControlsSessionManager as SessionManager,
+        GlobalSystemMediaTransportControlsSessionPlaybackStatus as PlaybackStatus,
+    };
+
+    /// Players are known by the AppUserModelID of their session.
+    pub fn pause_all() -> anyhow::Result<Vec<String>> {
+        let mut paused = vec![];
+        for session in SessionManager::RequestAsync()?.get()?.GetSessions()? {
+            if session.GetPlaybackInfo()?.PlaybackStatus()? == PlaybackStatus::Playing
+                && session.TryPauseAsync()?.get()?
+            {
+                paused.push(session.SourceAppUserModelId()?.to_string());
+            }
+        }
+        Ok(paused)
+    }
+
+    pub fn resume(paused: Vec<String>) -> anyhow::Result<()> {
+        for session in SessionManager::RequestAsync()?.get()?.GetSessions()? {
+            if paused.contains(&session.SourceAppUserModelId()?.to_string()) {
+                session.TryPlayAsync()?.get()?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod platform {
+    use zbus::{blocking::Connection, zvariant::OwnedValue};
+
+    const MPRIS_PREFIX: &str = "org.mpris.MediaPlayer2.";
+    const MPRIS_PATH: &str = "/org/mpris/MediaPlayer2";
+    const MPRIS_PLAYER: &str = "org.mpris.MediaPlayer2.Player";
+
+    pub fn pause_all() -> anyhow::Result<Vec<String>> {
+        pause_all_on(&Connection::session()?)
+    }
+
+    pub fn resume(paused: Vec<String>) -> anyhow::Result<()> {
+        resume_on(&Connection::session()?, paused)
+    }
+
+    /// Players are known by their bus names on the bus of `connection`.
+    pub(super) fn pause_all_on(connection: &Connection) -> anyhow::Result<Vec<String>> {
+        let names: Vec<String> = connection
+            .call_method(
+                Some("org.freedesktop.DBus"),
+                "/org/freedesktop/DBus",
+                Some("org.freedesktop.DBus"),
+                "ListNames",
+                &(),
+            )?
+            .body()?;
+
+        Ok(names
+            .into_iter()
+            .filter(|name| name.starts_with(MPRIS_PREFIX))
+            .filter(|name| match pause(connection, name) {
+                Ok(paused) => paused,
+                Err(e) => {
+                    tracing::error!("Failed to pause `{}`: {:?}", name, e);
+                    false
+                }
+            })
+            .collect())
+    }
+
+    /// Pauses the player at `name` if it is playing, returning whether it was.
+    fn pause(connection: &Connection, name: &str) -> anyhow::Result<bool> {
+        let status: OwnedValue = connection
+            .call_method(
+                Some(name),
+                MPRIS_PATH,
+                Some("org.freedesktop.DBus.Properties"),
+                "Get",
+                &(MPRIS_PLAYER, "PlaybackStatus"),
+            )?
+            .body()?;
+        if !matches!(<&str>::try_from(&status), Ok("Playing")) {
+            return Ok(false);
+        }
+
+        connection.call_method(Some(name), MPRIS_PATH, Some(MPRIS_PLAYER), "Pause", &())?;
+        Ok(true)
+    }
+
+    pub(super) fn resume_on(connection: &Connection, paused: Vec<String>) -> anyhow::Result<()> {
+        for name in paused {
+            connection.call_method(
+                Some(name.as_str()),
+                MPRIS_PATH,
+                Some(MPRIS_PLAYER),
+                "Play",
+                &(),
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(not(any(windows, target_os = "linux")))]
+mod platform {
+    pub fn pause_all() -> anyhow::Result<Vec<String>> {
+        Ok(vec![])
+    }
+
+    pub fn resume(_paused: Vec<String>) -> anyhow::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(all(test, target_os = "linux"))]
+mod tests {
+    use std::{
+        io::{BufRead, BufReader},
+        process::{Command, Stdio},
+        sync::{Arc, Mutex},
+    };
+
+    use zbus::{blocking::ConnectionBuilder, dbus_interface};
+
+    use super::platform;
+
+    struct FakePlayer {
+        status: Arc<Mutex<&'static str>>,
+    }
+
+    #[dbus_interface(name = "org.mpris.MediaPlayer2.Player")]
+    impl FakePlayer {
+        fn pause(&self) {
+            *self.status.lock().unwrap() = "Paused";
+        }
+
+        fn play(&self) {
+            *self.status.lock().unwrap() = "Playing";
+        }
+
+        #[dbus_interface(property)]
+        fn playback_status(&self) -> String {
+            self.status.lock().unwrap().to_string()
+        }
+    }
+
+    /// A player on a private session bus is paused while playing, and played
+    /// again on resuming.
+    #[test]
+    fn pauses_and_resumes_playing_players() {
+        let mut daemon = Command::new("dbus-daemon")
+            .args(["--session", "--nofork", "--print-address"])
+            .stdout(Stdio::piped())
+            .spawn()
+            .expect("dbus-daemon is needed for a private bus");
+        let mut address = String::new();
+        BufReader::new(daemon.stdout.take().unwrap())
+            .read_line(&mut address)
+            .unwrap();
+        let bus = || ConnectionBuilder::address(address.trim()).unwrap();
+
+        let status = Arc::new(Mutex::new("Playing"));
+        let _player = bus()
+            .name("org.mpris.MediaPlayer2.Fake")
+            .unwrap()
+            .serve_at(
+                "/org/mpris/MediaPlayer2",
+                FakePlayer {
+                    status: status.clone(),
+                },
+            )
+            .unwrap()
+            .build()
+            .unwrap();
+
+        let connection = bus().build().unwrap();
+        let paused = platform::pause_all_on(&connection).unwrap();
+        assert_eq!(paused, ["org.mpris.MediaPlayer2.Fake"]);
+        assert_eq!(*status.lock().unwrap(), "Paused");
+        // Players already paused are left to the user.
+        assert!(platform::pause_all_on(&connection).unwrap().is_empty());
+
+        platform::resume_on(&connection, paused).unwrap();
+        assert_eq!(*status.lock().unwrap(), "Playing");
+
+        daemon.kill().unwrap();
+        daemon.wait().unwrap();
+    }
+}
@@ -0,0 +1,194 @@
+//! Moon phase and crescent visibility.
+
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+
+use crate::astro;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct MoonInfo {
+    /// Time since the last conjunction.
+    pub age: Duration,
+    /// Illuminated fraction of the disk, from 0 to 1.
+    pub illumination: f64,
+    pub waxing: bool,
+    pub rise: Option<DateTime<Utc>>,
+    pub set: Option<DateTime<Utc>>,
+    pub last_conjunction: DateTime<Utc>,
+    pub next_conjunction: DateTime<Utc>,
+    /// Visibility on the evenings around the nearest conjunction, when it is
+    /// close enough to matter.
+    pub crescent: Vec<CrescentVisibility>,
+}
+
+/// Days around a conjunction in which crescent visibility is estimated.
+const CRESCENT_WATCH_DAYS: i64 = 2;
+
+impl MoonInfo {
+    pub fn at(now: DateTime<Utc>, coordinates: adhaan::Coordinates) -> MoonInfo {
+        let lunation = astro::lunation_at(now);
+        let last_conjunction = astro::new_moon(lunation);
+        let next_conjunction = astro::new_moon(lunation + 1);
+
+        let (illumination, waxing) = illumination_at(now);
+
+        let local_midnight =
+            astro::local_midnight(astro::local_date(now, coordinates), coordinates);
+        let (rise, set) = astro::crossings(local_midnight, astro::HORIZON, |time| {
+            astro::moon_horizontal(time, coordinates).altitude
+        });
+
+        let conjunction = if next_conjunction - now < now - last_conjunction {
+            next_conjunction
+        } else {
+            last_conjunction
+        };
+        let crescent = if (conjunction - now).num_days().abs() <= CRESCENT_WATCH_DAYS {
+            let conjunction_date = astro::local_date(conjunction, coordinates);
+            (0..2)
+                .filter_map(|day| {
+                    crescent_visibility(
+                        conjunction_date + Duration::days(day),
+                        conjunction,
+                        coordinates,
+                    )
+                })
+                .collect()
+        } else {
+            vec![]
+        };
+
+        MoonInfo {
+            age: now - last_conjunction,
+            illumination,
+            waxing,
+            rise,
+            set,
+            last_conjunction,
+            next_conjunction,
+            crescent,
+        }
+    }
+
+    pub fn phase_name(&self) -> &'static str {
+        let fraction = self.age.num_minutes() as f64 / (astro::MEAN_SYNODIC_MONTH * 24.0 * 60.0);
+        match fraction {
+            f if f < 0.034 => "New moon",
+            f if f < 0.216 => "Waxing crescent",
+            f if f < 0.284 => "First quarter",
+            f if f < 0.466 => "Waxing gibbous",
+            f if f < 0.534 => "Full moon",
+            f if f < 0.716 => "Waning gibbous",
+            f if f < 0.784 => "Last quarter",
+            f if f < 0.966 => "Waning crescent",
+            _ => "New moon",
+        }
+    }
+}
+
+/// Illuminated fraction of the moon and whether it is waxing.
+pub fn illumination_at(time: DateTime<Utc>) -> (f64, bool) {
+    let (moon_longitude, moon_latitude, _) = astro::moon_ecliptic(time);
+    let difference = (moon_longitude - astro::sun_longitude(time)).rem_euclid(360.0);
+    let cos_elongation = moon_latitude.to_radians().cos() * difference.to_radians().cos();
+    ((1.0 - cos_elongation) / 2.0, difference < 180.0)
+}
+
+/// Visibility classes of Yallop's criterion.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum YallopClass {
+    A,
+    B,
+    C,
+    D,
+    E,
+    F,
+}
+
+impl YallopClass {
+    fn from_q(q: f64) -> YallopClass {
+        match q {
+            q if q > 0.216 => YallopClass::A,
+            q if q > -0.014 => YallopClass::B,
+            q if q > -0.160 => YallopClass::C,
+            q if q > -0.232 => YallopClass::D,
+            q if q > -0.293 => YallopClass::E,
+            _ => YallopClass::F,
+        }
+    }
+
+    pub fn description(self) -> &'static str {
+        match self {
+            YallopClass::A => "Easily visible to the naked eye",
+            YallopClass::B => "Visible to the naked eye under perfect conditions",
+            YallopClass::C => "May need optical aid to find, then visible to the naked eye",
+            YallopClass::D => "Visible with optical aid only",
+            YallopClass::E => "Not visible even with a telescope",
+            YallopClass::F => "Not visible, below the Danjon limit",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CrescentVisibility {
+    /// Best time to look, four ninths of the lag after sunset.
+    pub best_time: DateTime<Utc>,
+    pub sunset: DateTime<Utc>,
+    pub moonset: Option<DateTime<Utc>>,
+    pub moon_age: Duration,
+    pub q: f64,
+    pub class: YallopClass,
+}
+
+/// Yallop's estimate of crescent visibility on the evening of `date`, if the
+/// sun sets after `conjunction` on that day.
+pub fn crescent_visibility(
+    date: NaiveDate,
+    conjunction: DateTime<Utc>,
+    coordinates: adhaan::Coordinates,
+) -> Option<CrescentVisibility> {
+    let sunset = astro::sunset(date, coordinates).filter(|sunset| *sunset > conjunction)?;
+    let moonset = astro::crossings(sunset, astro::HORIZON, |time| {
+        astro::moon_horizontal(time, coordinates).altitude
+    })
+    .1
+    .filter(|moonset| *moonset - sunset < Duration::hours(12));
+
+    let best_time = match moonset {
+        Some(moonset) => sunset + (moonset - sunset) * 4 / 9,
+        None => sunset,
+    };
+
+    let sun = astro::sun_position(best_time);
+    let moon = astro::moon_position(best_time);
+    let (_, _, parallax) = astro::moon_ecliptic(best_time);
+
+    let arc_of_vision = astro::to_horizontal(moon, best_time, coordinates).altitude
+        - astro::to_horizontal(sun, best_time, coordinates).altitude;
+    let arc_of_light = (sun.declination.to_radians().sin() * moon.declination.to_radians().sin()
+        + sun.declination.to_radians().cos()
+            * moon.declination.to_radians().cos()
+            * (sun.right_ascension - moon.right_ascension)
+                .to_radians()
+                .cos())
+    .acos();
+
+    let topocentric_altitude = astro::moon_horizontal(best_time, coordinates).altitude;
+    let semidiameter = 0.27245
+        * parallax
+        * 60.0
+        * (1.0 + topocentric_altitude.to_radians().sin() * parallax.to_radians().sin());
+    let width = semidiameter * (1.0 - arc_of_light.cos());
+
+    let q = (arc_of_vision
+        - (11.8371 - 6.3226 * width + 0.7319 * width.powi(2) - 0.1018 * width.powi(3)))
+        / 10.0;
+
+    Some(CrescentVisibility {
+        best_time,
+        sunset,
+        moonset,
+        moon_age: best_time - conjunction,
+        q,
+        class: YallopClass::from_q(q),
+    })
+}
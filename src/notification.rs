@@ -0,0 +1,349 @@
+//! Desktop notifications, through toasts on Windows and the freedesktop
+//! notification service on Linux.
+
+/// A button offered on a notification.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    Snooze(u16),
+    Prayed,
+}
+
+impl Action {
+    pub fn label(self) -> String {
+        match self {
+            Action::Snooze(minutes) => format!("Snooze {} min", minutes),
+            Action::Prayed => "Prayed".into(),
+        }
+    }
+}
+
+/// Registers the app with the desktop for notifications, once at start.
+pub fn register() {
+    if let Err(e) = platform::register() {
+        tracing::error!("Failed to register for notifications: {:?}", e);
+    }
+}
+
+pub fn show(summary: &str, body: &str) {
+    show_with_actions(summary, body, &[], false, |_| {})
+}
+
+/// Shows a notification offering `actions`, calling `on_action` from another
+/// thread when one is chosen. A `silent` one asks the desktop not to play its
+/// sound.
+pub fn show_with_actions(
+    summary: &str,
+    body: &str,
+    actions: &[Action],
+    silent: bool,
+    on_action: impl Fn(Action) + Send + 'static,
+) {
+    if let Err(e) = platform::show(summary, body, actions, silent, Box::new(on_action)) {
+        tracing::error!("Failed to show notification: {:?}", e);
+    }
+}
+
+type OnAction = Box<dyn Fn(Action) + Send>;
+
+#[cfg(windows)]
+mod platform {
+    use std::cell::RefCell;
+
+    use windows::{
+        core::{IInspectable, Interface, HSTRING},
+        Data::Xml::Dom::XmlDocument,
+        Foundation::TypedEventHandler,
+        Win32::{
+            System::Registry::{RegSetKeyValueW, HKEY_CURRENT_USER, REG_SZ},
+            UI::Shell::SetCurrentProcessExplicitAppUserModelID,
+        },
+        UI::Notifications::{ToastActivatedEventArgs, ToastNotification, ToastNotificationManager},
+    };
+
+    use super::{Action, OnAction};
+    use crate::{ui_main, utils};
+
+    /// The AppUserModelID toasts are raised under, which gives them the name and
+    /// icon of the app and routes their actions back to it.
+    const APP_ID: &str = "RagibHasin.AdhaanGUI";
+
+    /// Unpackaged applications are registered by a key under `AppUserModelId`
+    /// instead of a Start menu shortcut.
+    pub fn register() -> anyhow::Result<()> {
+        let icon = utils::appdata_dir().join("icon.ico");
+        if !icon.exists() {
+            std::fs::create_dir_all(utils::appdata_dir())?;
+            std::fs::write(&icon, ui_main::TRAY_ICON)?;
+        }
+
+        let key = HSTRING::from(format!("Software\\Classes\\AppUserModelId\\{}", APP_ID));
+        set_registry_string(&key, "DisplayName", "Adhaan")?;
+        set_registry_string(&key, "IconUri", &icon.to_string_lossy())?;
+        unsafe { SetCurrentProcessExplicitAppUserModelID(&HSTRING::from(APP_ID))? };
+        Ok(())
+    }
+
+    fn set_registry_string(key: &HSTRING, name: &str, value: &str) -> anyhow::Result<()> {
+        let data = value.encode_utf16().chain([0]).collect::<Vec<u16>>();
+        unsafe {
+            RegSetKeyValueW(
+                HKEY_CURRENT_USER,
+                key,
+                &HSTRING::from(name),
+                REG_SZ.0,
+                data.as_ptr() as _,
+                (data.len() * 2) as _,
+            )
+        }
+        .to_hresult()
+        .ok()?;
+        Ok(())
+    }
+
+    /// Toasts with actions are kept alive for their handlers to be called.
+    const KEPT_TOASTS: usize = 16;
+
+    thread_local! {
+        static TOASTS: RefCell<Vec<ToastNotification>> = RefCell::new(vec![]);
+    }
+
+    pub fn show(
+        summary: &str,
+        body: &str,
+        actions: &[Action],
+        silent: bool,
+        on_action: OnAction,
+    ) -> anyhow::Result<()> {
+        let buttons = actions
+            .iter()
+            .enumerate()
+            .map(|(i, action)| {
+                format!(
+                    r#"<action content="{}" arguments="{}" activationType="foreground"/>"#,
+                    escape(&action.label()),
+                    i
+                )
+            })
+            .collect::<String>();
+        let xml = XmlDocument::new()?;
+        xml.LoadXml(&HSTRING::from(format!(
+            r#"<toast><visual><binding template="ToastGeneric"><text>{}</text><text>{}</text></binding></visual><actions>{}</actions><audio silent="{}"/></toast>"#,
+            escape(summary),
+            escape(body),
+            buttons,
+            silent,
+        )))?;
+        let toast = ToastNotification::CreateToastNotification(&xml)?;
+
+        if !actions.is_empty() {
+            let actions = actions.to_vec();
+            toast.Activated(&TypedEventHandler::<ToastNotification, IInspectable>::new(
+                move |_, args| {
+                    let chosen = match args {
+                        Some(args) => args.cast::<ToastActivatedEventArgs>()?.Arguments()?,
+                        None => return Ok(()),
+                    };
+                    if let Some(&action) = chosen
+                        .to_string()
+                        .parse::<usize>()
+                        .ok()
+                        .and_then(|i| actions.get(i))
+                    {
+                        on_action(action);
+                    }
+                    Ok(())
+                },
+            ))?;
+            TOASTS.with(|toasts| {
+                let mut toasts = toasts.borrow_mut();
+                toasts.push(toast.clone());
+                let excess = toasts.len().saturating_sub(KEPT_TOASTS);
+                toasts.drain(..excess);
+            });
+        }
+
+        ToastNotificationManager::CreateToastNotifierWithId(&HSTRING::from(APP_ID))?
+            .Show(&toast)?;
+        Ok(())
+    }
+
+    fn escape(text: &str) -> String {
+        text.replace('&', "&amp;")
+            .replace('<', "&lt;")
+            .replace('>', "&gt;")
+            .replace('"', "&quot;")
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod platform {
+    use std::{
+        collections::HashMap,
+        sync::{mpsc, Arc, Mutex},
+    };
+
+    use zbus::{
+        blocking::{Connection, Proxy},
+        zvariant::Value,
+    };
+
+    use super::{Action, OnAction};
+
+    const DESTINATION: &str = "org.freedesktop.Notifications";
+    const PATH: &str = "/org/freedesktop/Notifications";
+    const INTERFACE: &str = "org.freedesktop.Notifications";
+
+    /// The desktop knows the app by the name sent with each notification.
+    pub fn register() -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    /// The actions offered on notifications still shown, by their ids.
+    type Pending = HashMap<u32, (Vec<Action>, OnAction)>;
+
+    /// The connection all notifications are sent over, with a single listener
+    /// to their actions.
+    struct Shared {
+        connection: Connection,
+        pending: Arc<Mutex<Pending>>,
+    }
+
+    static SHARED: Mutex<Option<Shared>> = Mutex::new(None);
+
+    impl Shared {
+        fn connect() -> anyhow::Result<Shared> {
+            let connection = Connection::session()?;
+            let pending = Arc::new(Mutex::new(Pending::new()));
+            let (subscribed_tx, subscribed_rx) = mpsc::channel();
+            {
+                let connection = connection.clone();
+                let pending = pending.clone();
+                std::thread::spawn(move || {
+                    if let Err(e) = listen(&connection, &pending, subscribed_tx) {
+                        tracing::error!("Failed to listen to notification actions: {:?}", e);
+                    }
+                });
+            }
+            // No notification is sent before its actions can be heard.
+            subscribed_rx.recv()?;
+            Ok(Shared {
+                connection,
+                pending,
+            })
+        }
+    }
+
+    pub fn show(
+        summary: &str,
+        body: &str,
+        actions: &[Action],
+        silent: bool,
+        on_action: OnAction,
+    ) -> anyhow::Result<()> {
+        // Actions are sent as pairs of key and label, the key being the index.
+        let keys = (0..actions.len())
+            .map(|i| i.to_string())
+            .collect::<Vec<_>>();
+        let labels = actions
+            .iter()
+            .map(|action| action.label())
+            .collect::<Vec<_>>();
+        let pairs = keys
+            .iter()
+            .zip(&labels)
+            .flat_map(|(key, label)| [key.as_str(), label.as_str()])
+            .collect::<Vec<_>>();
+
+        let mut shared = SHARED.lock().unwrap();
+        if shared.is_none() {
+            *shared = Some(Shared::connect()?);
+        }
+        let shared = shared.as_ref().unwrap();
+
+        // Held over the call, so that an action is not looked up before its
+        // notification is pending.
+        let mut pending = shared.pending.lock().unwrap();
+        let id: u32 = shared
+            .connection
+            .call_method(
+                Some(DESTINATION),
+                PATH,
+                Some(INTERFACE),
+                "Notify",
+                &(
+                    "Adhaan",
+                    0u32,
+                    "",
+                    summary,
+                    body,
+                    pairs,
+                    HashMap::from([("suppress-sound", Value::from(silent))]),
+                    -1i32,
+                ),
+            )?
+            .body()?;
+
+        if !actions.is_empty() {
+            pending.insert(id, (actions.to_vec(), on_action));
+        }
+        Ok(())
+    }
+
+    /// Calls the handlers of actions chosen on pending notifications, and forgets
+    /// notifications once closed, for as long as the app runs.
+    fn listen(
+        connection: &Connection,
+        pending: &Mutex<Pending>,
+        subscribed: mpsc::Sender<()>,
+    ) -> anyhow::Result<()> {
+        let proxy = Proxy::new(connection, DESTINATION, PATH, INTERFACE)?;
+        let signals = proxy.receive_all_signals()?;
+        let _ = subscribed.send(());
+        for message in signals {
+            let member = message.member();
+            match member.as_ref().map(|member| member.as_str()) {
+                Some("ActionInvoked") => {
+                    let (id, key): (u32, String) = match message.body() {
+                        Ok(body) => body,
+                        Err(_) => continue,
+                    };
+                    let chosen = pending.lock().unwrap().remove(&id);
+                    if let Some((actions, on_action)) = chosen {
+                        if let Some(&action) =
+                            key.parse::<usize>().ok().and_then(|i| actions.get(i))
+                        {
+                            on_action(action);
+                        }
+                    }
+                }
+                Some("NotificationClosed") => {
+                    if let Ok((id, _reason)) = message.body::<(u32, u32)>() {
+                        pending.lock().unwrap().remove(&id);
+                    }
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(not(any(windows, target_os = "linux")))]
+mod platform {
+    use super::{Action, OnAction};
+
+    pub fn register() -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    pub fn show(
+        summary: &str,
+        body: &str,
+        _actions: &[Action],
+        _silent: bool,
+        _on_action: OnAction,
+    ) -> anyhow::Result<()> {
+        tracing::info!("{}: {}", summary, body);
+        Ok(())
+    }
+}
@@ -0,0 +1,65 @@
+//! Waqts marked as prayed, kept in `prayed.toml` across restarts.
+
+use adhaan::Prayer;
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize, Deserialize, Default)]
+struct PrayedFile {
+    #[serde(default)]
+    prayed: Vec<Mark>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Mark {
+    date: NaiveDate,
+    prayer: String,
+}
+
+/// The prayers marks are recorded against, by their names in the file.
+const NAMES: [(Prayer, &str); 6] = [
+    (Prayer::Fajr, "fajr"),
+    (Prayer::Dhuhr, "dhuhr"),
+    (Prayer::AsrAwwal, "asr"),
+    (Prayer::Maghrib, "maghrib"),
+    (Prayer::Isha, "isha"),
+    (Prayer::Qiyam, "qiyam"),
+];
+
+/// Loads the marks, none if there is no file yet. Marks of unknown prayers are
+/// skipped.
+pub fn load() -> anyhow::Result<Vec<(NaiveDate, Prayer)>> {
+    let path = crate::utils::prayed_path();
+    if !path.exists() {
+        return Ok(vec![]);
+    }
+    Ok(toml::from_slice::<PrayedFile>(&std::fs::read(path)?)?
+        .prayed
+        .into_iter()
+        .filter_map(|mark| {
+            NAMES
+                .iter()
+                .find(|(_, name)| *name == mark.prayer)
+                .map(|&(prayer, _)| (mark.date, prayer))
+        })
+        .collect())
+}
+
+pub fn save(prayed: &[(NaiveDate, Prayer)]) -> anyhow::Result<()> {
+    let file = PrayedFile {
+        prayed: prayed
+            .iter()
+            .filter_map(|&(date, prayer)| {
+                NAMES
+                    .iter()
+                    .find(|(named, _)| *named == prayer)
+                    .map(|(_, name)| Mark {
+                        date,
+                        prayer: name.to_string(),
+                    })
+            })
+            .collect(),
+    };
+    std::fs::write(crate::utils::prayed_path(), toml::to_string(&file)?)?;
+    Ok(())
+}
@@ -0,0 +1,137 @@
+//! Whether the user should be left undisturbed, as the desktop tells.
+
+/// Whether the desktop is in do-not-disturb, or on Windows, running a
+/// full-screen application or presentation.
+pub fn do_not_disturb() -> bool {
+    platform::do_not_disturb().unwrap_or_else(|e| {
+        tracing::error!("Failed to query do-not-disturb: {:?}", e);
+        false
+    })
+}
+
+#[cfg(windows)]
+mod platform {
+    use winapi::um::shellapi::{SHQueryUserNotificationState, QUNS_ACCEPTS_NOTIFICATIONS};
+
+    /// Focus Assist, full-screen applications and presentations all show up as
+    /// the shell not accepting notifications.
+    pub fn do_not_disturb() -> anyhow::Result<bool> {
+        let mut state = 0;
+        let result = unsafe { SHQueryUserNotificationState(&mut state) };
+        if result < 0 {
+            anyhow::bail!("SHQueryUserNotificationState failed with {:#x}", result);
+        }
+        Ok(state != QUNS_ACCEPTS_NOTIFICATIONS)
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod platform {
+    use zbus::{blocking::Connection, zvariant::OwnedValue};
+
+    pub fn do_not_disturb() -> anyhow::Result<bool> {
+        Ok(inhibited_on(&Connection::session()?))
+    }
+
+    /// Notification servers implementing the `Inhibited` property of the
+    /// freedesktop specification report do-not-disturb through it.
+    pub(super) fn inhibited_on(connection: &Connection) -> bool {
+        let inhibited: zbus::Result<OwnedValue> = connection
+            .call_method(
+                Some("org.freedesktop.Notifications"),
+                "/org/freedesktop/Notifications",
+                Some("org.freedesktop.DBus.Properties"),
+                "Get",
+                &("org.freedesktop.Notifications", "Inhibited"),
+            )
+            .and_then(|reply| reply.body());
+        match inhibited {
+            Ok(inhibited) => bool::try_from(&inhibited).unwrap_or(false),
+            // Older servers do not know the property.
+            Err(_) => false,
+        }
+    }
+}
+
+#[cfg(not(any(windows, target_os = "linux")))]
+mod platform {
+    pub fn do_not_disturb() -> anyhow::Result<bool> {
+        Ok(false)
+    }
+}
+
+#[cfg(all(test, target_os = "linux"))]
+mod tests {
+    use std::{
+        io::{BufRead, BufReader},
+        process::{Command, Stdio},
+    };
+
+    use zbus::{blocking::ConnectionBuilder, dbus_interface};
+
+    use super::platform;
+
+    struct FakeServer {
+        inhibited: bool,
+    }
+
+    #[dbus_interface(name = "org.freedesktop.Notifications")]
+    impl FakeServer {
+        #[dbus_interface(property)]
+        fn inhibited(&self) -> bool {
+            self.inhibited
+        }
+    }
+
+    struct OlderServer;
+
+    #[dbus_interface(name = "org.freedesktop.Notifications")]
+    impl OlderServer {
+        fn get_server_information(&self) -> (String, String, String, String) {
+            ("Older".into(), "".into(), "1".into(), "1.2".into())
+        }
+    }
+
+    /// Do-not-disturb follows the `Inhibited` property of the notification
+    /// server on a private session bus, and is off when the server lacks it.
+    #[test]
+    fn follows_the_inhibited_property() {
+        let mut daemon = Command::new("dbus-daemon")
+            .args(["--session", "--nofork", "--print-address"])
+            .stdout(Stdio::piped())
+            .spawn()
+            .expect("dbus-daemon is needed for a private bus");
+        let mut address = String::new();
+        BufReader::new(daemon.stdout.take().unwrap())
+            .read_line(&mut address)
+            .unwrap();
+        let bus = || ConnectionBuilder::address(address.trim()).unwrap();
+        let connection = bus().build().unwrap();
+
+        assert!(!platform::inhibited_on(&connection));
+
+        for inhibited in [true, false] {
+            let server = bus()
+                .name("org.freedesktop.Notifications")
+                .unwrap()
+                .serve_at("/org/freedesktop/Notifications", FakeServer { inhibited })
+                .unwrap()
+                .build()
+                .unwrap();
+            assert_eq!(platform::inhibited_on(&connection), inhibited);
+            drop(server);
+        }
+
+        let _server = bus()
+            .name("org.freedesktop.Notifications")
+            .unwrap()
+            .serve_at("/org/freedesktop/Notifications", OlderServer)
+            .unwrap()
+            .build()
+            .unwrap();
+        assert!(!platform::inhibited_on(&connection));
+
+        daemon.kill().unwrap();
+        daemon.wait().unwrap();
+    }
+}
@@ -0,0 +1,358 @@
+//! Monthly timetables laid out for printing on mosque noticeboards, as HTML
+//! and PDF, made without going online.
+
+use std::{fmt::Write as _, fs::File, io::BufWriter, path::Path};
+
+use chrono::{DateTime, Datelike, Local, NaiveDate, Utc, Weekday};
+use printpdf::{
+    image_crate::{self, DynamicImage, GenericImageView},
+    Color, Greyscale, Image, ImageTransform, IndirectFontRef, Line, Mm, PdfDocument,
+    PdfLayerReference, Point,
+};
+
+use crate::{
+    app_state::AppState,
+    config::{AsrConfig, Orientation, Paper, PrintConfig},
+    timetable::{self, Day},
+};
+
+const COLUMNS: [&str; 9] = [
+    "Date", "Day", "Hijri", "Fajr", "Sunrise", "Dhuhr", "Asr", "Maghrib", "Isha",
+];
+/// Relative widths of `COLUMNS` in the PDF.
+const COLUMN_WEIGHTS: [f64; 9] = [0.8, 0.9, 1.8, 1.0, 1.0, 1.0, 1.0, 1.0, 1.0];
+
+const MARGIN: f64 = 12.0;
+const LOGO_HEIGHT: f64 = 20.0;
+const HEADER_HEIGHT: f64 = 30.0;
+
+/// A page of the timetable: the days of a month within the printed range.
+struct Page {
+    month: String,
+    hijri: String,
+    days: Vec<Day>,
+}
+
+/// What heads every page.
+struct Heading {
+    title: String,
+    details: String,
+}
+
+fn heading(data: &AppState) -> Heading {
+    let config = &data.config;
+    Heading {
+        title: config
+            .location_name
+            .clone()
+            .unwrap_or_else(|| "Prayer times".into()),
+        details: format!(
+            "{} method, Asr at {}, {:.4}, {:.4}",
+            config.method.label(),
+            match config.asr {
+                AsrConfig::AsrStartsAtAsrThaani(_) => "twice the shadow",
+                _ => "the shadow's length",
+            },
+            config.coordinates.latitude,
+            config.coordinates.longitude,
+        ),
+    }
+}
+
+fn pages(data: &AppState, from: NaiveDate, to: NaiveDate) -> Vec<Page> {
+    let mut pages = vec![];
+    let mut month = from;
+    while month <= to {
+        let (first, last) = timetable::month_bounds(month);
+        let days = timetable::days(data, first.max(from), last.min(to));
+        pages.push(Page {
+            month: month.format("%B %Y").to_string(),
+            hijri: timetable::hijri_span(days[0].hijri, days[days.len() - 1].hijri),
+            days,
+        });
+        month = timetable::next_month(month);
+    }
+    pages
+}
+
+fn is_jumuah(day: &Day) -> bool {
+    day.date.weekday() == Weekday::Fri
+}
+
+fn cells(day: &Day, asr: AsrConfig) -> [String; 9] {
+    let time = |time: DateTime<Utc>| time.with_timezone(&Local).format("%I:%M %p").to_string();
+    [
+        day.date.format("%d").to_string(),
+        if is_jumuah(day) {
+            "Jumu'ah".into()
+        } else {
+            day.date.format("%a").to_string()
+        },
+        format!("{} {}", day.hijri.day, day.hijri.month_name()),
+        time(day.fajr),
+        time(day.sunrise),
+        time(day.dhuhr),
+        time(day.asr(asr)),
+        time(day.maghrib),
+        time(day.isha),
+    ]
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// The logo as a data URL, to keep the page self-contained.
+fn logo_url(path: &Path) -> anyhow::Result<String> {
+    let mime = match path
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .map(|extension| extension.to_ascii_lowercase())
+        .as_deref()
+    {
+        Some("png") => "image/png",
+        Some("jpg" | "jpeg") => "image/jpeg",
+        Some("gif") => "image/gif",
+        Some("svg") => "image/svg+xml",
+        Some("webp") => "image/webp",
+        _ => anyhow::bail!("Unknown image type of {}", path.display()),
+    };
+    Ok(format!(
+        "data:{};base64,{}",
+        mime,
+        base64::encode(std::fs::read(path)?)
+    ))
+}
+
+pub fn html(data: &AppState, from: NaiveDate, to: NaiveDate) -> anyhow::Result<String> {
+    let config = &data.config;
+    let heading = heading(data);
+    let logo = config.print.logo.as_deref().map(logo_url).transpose()?;
+
+    let mut html = String::new();
+    writeln!(html, "<!DOCTYPE html>")?;
+    writeln!(html, "<html>\n<head>\n<meta charset=\"utf-8\">")?;
+    writeln!(html, "<title>{}</title>", escape_html(&heading.title))?;
+    writeln!(
+        html,
+        "<style>\n\
+         @page {{ size: {} {}; margin: {}mm; }}\n\
+         body {{ font-family: \"{}\", sans-serif; color: #222; margin: 0; }}\n\
+         section {{ break-after: page; }}\n\
+         section:last-child {{ break-after: auto; }}\n\
+         header {{ display: flex; align-items: center; gap: 6mm; margin-bottom: 4mm; }}\n\
+         header img {{ height: {}mm; }}\n\
+         h1 {{ margin: 0; font-size: 20pt; }}\n\
+         h2 {{ margin: 0; font-size: 13pt; font-weight: normal; }}\n\
+         .details {{ font-size: 8pt; color: #666; }}\n\
+         table {{ width: 100%; border-collapse: collapse; font-size: 9.5pt; }}\n\
+         th, td {{ border: 1px solid #999; padding: 0.8mm 1.5mm; text-align: center; }}\n\
+         th {{ background: #ddd; }}\n\
+         tr.jumuah td {{ background: #e6efe0; font-weight: bold; }}\n\
+         </style>",
+        match config.print.paper {
+            Paper::A4 => "A4",
+            Paper::Letter => "letter",
+        },
+        match config.print.orientation {
+            Orientation::Portrait => "portrait",
+            Orientation::Landscape => "landscape",
+        },
+        MARGIN,
+        escape_html(&config.font),
+        LOGO_HEIGHT,
+    )?;
+    writeln!(html, "</head>\n<body>")?;
+
+    for page in pages(data, from, to) {
+        writeln!(html, "<section>\n<header>")?;
+        if let Some(logo) = &logo {
+            writeln!(html, "<img src=\"{}\" alt=\"\">", logo)?;
+        }
+        writeln!(
+            html,
+            "<div>\n<h1>{}</h1>\n<h2>{} &middot; {}</h2>\n<div class=\"details\">{}</div>\n</div>",
+            escape_html(&heading.title),
+            page.month,
+            escape_html(&page.hijri),
+            escape_html(&heading.details),
+        )?;
+        writeln!(html, "</header>\n<table>\n<tr>")?;
+        for column in COLUMNS {
+            writeln!(html, "<th>{}</th>", column)?;
+        }
+        writeln!(html, "</tr>")?;
+        for day in &page.days {
+            if is_jumuah(day) {
+                writeln!(html, "<tr class=\"jumuah\">")?;
+            } else {
+                writeln!(html, "<tr>")?;
+            }
+            for cell in cells(day, config.asr) {
+                writeln!(html, "<td>{}</td>", escape_html(&cell))?;
+            }
+            writeln!(html, "</tr>")?;
+        }
+        writeln!(html, "</table>\n</section>")?;
+    }
+    writeln!(html, "</body>\n</html>")?;
+    Ok(html)
+}
+
+fn rect(layer: &PdfLayerReference, x: f64, y: f64, width: f64, height: f64) {
+    layer.add_shape(Line {
+        points: vec![
+            (Point::new(Mm(x), Mm(y)), false),
+            (Point::new(Mm(x + width), Mm(y)), false),
+            (Point::new(Mm(x + width), Mm(y + height)), false),
+            (Point::new(Mm(x), Mm(y + height)), false),
+        ],
+        is_closed: true,
+        has_fill: true,
+        has_stroke: true,
+        is_clipping_path: false,
+    });
+}
+
+struct Fonts {
+    regular: IndirectFontRef,
+    bold: IndirectFontRef,
+}
+
+/// Embedded in the PDF, as the fonts built into PDF readers cover little past
+/// Latin-1 and miss the letters of transliterated Hijri months.
+static REGULAR_FONT: &[u8] = include_bytes!("../resources/fonts/DejaVuSans.ttf");
+static BOLD_FONT: &[u8] = include_bytes!("../resources/fonts/DejaVuSans-Bold.ttf");
+
+fn pdf_page(
+    layer: &PdfLayerReference,
+    fonts: &Fonts,
+    heading: &Heading,
+    page: &Page,
+    logo: Option<&DynamicImage>,
+    config: &PrintConfig,
+    asr: AsrConfig,
+) {
+    let (width, height) = config.page_size();
+    let black = Color::Greyscale(Greyscale::new(0.0, None));
+    let top = height - MARGIN;
+
+    let mut text_x = MARGIN;
+    if let Some(logo) = logo {
+        // Scaled by the resolution to stand `LOGO_HEIGHT` tall.
+        let dpi = logo.height() as f64 * 25.4 / LOGO_HEIGHT;
+        Image::from_dynamic_image(logo).add_to_layer(
+            layer.clone(),
+            ImageTransform {
+                translate_x: Some(Mm(MARGIN)),
+                translate_y: Some(Mm(top - LOGO_HEIGHT)),
+                dpi: Some(dpi),
+                ..Default::default()
+            },
+        );
+        text_x += logo.width() as f64 * 25.4 / dpi + 6.0;
+    }
+
+    layer.set_fill_color(black.clone());
+    layer.use_text(&heading.title, 18.0, Mm(text_x), Mm(top - 7.0), &fonts.bold);
+    layer.use_text(
+        format!("{} - {}", page.month, page.hijri),
+        12.0,
+        Mm(text_x),
+        Mm(top - 14.0),
+        &fonts.regular,
+    );
+    layer.use_text(
+        &heading.details,
+        8.0,
+        Mm(text_x),
+        Mm(top - 19.0),
+        &fonts.regular,
+    );
+
+    let table_width = width - 2.0 * MARGIN;
+    let weights: f64 = COLUMN_WEIGHTS.iter().sum();
+    let widths = COLUMN_WEIGHTS.map(|weight| weight / weights * table_width);
+    let rows = page.days.len() + 1;
+    let row_height = ((top - HEADER_HEIGHT - MARGIN) / rows as f64).min(7.5);
+    let font_size = (row_height * 1.4).min(10.0);
+
+    layer.set_outline_color(Color::Greyscale(Greyscale::new(0.6, None)));
+    layer.set_outline_thickness(0.5);
+    let mut y = top - HEADER_HEIGHT;
+    let mut row = |cells: &[String], fill: f64, font: &IndirectFontRef| {
+        y -= row_height;
+        let mut x = MARGIN;
+        for (cell, width) in cells.iter().zip(widths) {
+            layer.set_fill_color(Color::Greyscale(Greyscale::new(fill, None)));
+            rect(layer, x, y, width, row_height);
+            layer.set_fill_color(black.clone());
+            layer.use_text(
+                cell,
+                font_size,
+                Mm(x + 1.5),
+                Mm(y + (row_height - font_size * 0.3528) / 2.0 + 0.5),
+                font,
+            );
+            x += width;
+        }
+    };
+
+    row(&COLUMNS.map(String::from), 0.85, &fonts.bold);
+    for day in &page.days {
+        if is_jumuah(day) {
+            row(&cells(day, asr), 0.92, &fonts.bold);
+        } else {
+            row(&cells(day, asr), 1.0, &fonts.regular);
+        }
+    }
+}
+
+pub fn write_pdf(
+    data: &AppState,
+    from: NaiveDate,
+    to: NaiveDate,
+    path: &Path,
+) -> anyhow::Result<()> {
+    let config = &data.config.print;
+    let heading = heading(data);
+    let logo = config
+        .logo
+        .as_deref()
+        .map(image_crate::open)
+        .transpose()?
+        // Transparency is not carried over into PDF images.
+        .map(|logo| DynamicImage::ImageRgb8(logo.to_rgb8()));
+    let (width, height) = config.page_size();
+
+    let (document, first_page, first_layer) =
+        PdfDocument::new(&heading.title, Mm(width), Mm(height), "Timetable");
+    let fonts = Fonts {
+        regular: document.add_external_font(REGULAR_FONT)?,
+        bold: document.add_external_font(BOLD_FONT)?,
+    };
+
+    for (i, page) in pages(data, from, to).iter().enumerate() {
+        let (page_index, layer_index) = if i == 0 {
+            (first_page, first_layer)
+        } else {
+            document.add_page(Mm(width), Mm(height), "Timetable")
+        };
+        let layer = document.get_page(page_index).get_layer(layer_index);
+        pdf_page(
+            &layer,
+            &fonts,
+            &heading,
+            page,
+            logo.as_ref(),
+            config,
+            data.config.asr,
+        );
+    }
+
+    document.save(&mut BufWriter::new(File::create(path)?))?;
+    Ok(())
+}
@@ -0,0 +1,106 @@
+//! Direction and distance to the Kaaba, and when the sun points the way.
+
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+
+use crate::{astro, config::KAABA_COORDINATES};
+
+/// Mean radius of the earth in kilometres.
+const EARTH_RADIUS: f64 = 6371.0088;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct QiblaInfo {
+    /// Initial great-circle bearing to the Kaaba, in degrees eastwards from
+    /// true north.
+    pub bearing: f64,
+    /// Great-circle distance to the Kaaba in kilometres.
+    pub distance: f64,
+    /// When the sun stands in the direction of the Qibla, to face it.
+    pub sun_ahead: Vec<DateTime<Utc>>,
+    /// When the sun stands opposite the Qibla, so shadows point to it.
+    pub sun_behind: Vec<DateTime<Utc>>,
+}
+
+impl QiblaInfo {
+    pub fn at(date: NaiveDate, coordinates: adhaan::Coordinates) -> QiblaInfo {
+        let bearing = bearing(coordinates);
+        QiblaInfo {
+            bearing,
+            distance: distance(coordinates),
+            sun_ahead: sun_at_azimuth(date, coordinates, bearing),
+            sun_behind: sun_at_azimuth(date, coordinates, (bearing + 180.0).rem_euclid(360.0)),
+        }
+    }
+}
+
+pub fn bearing(from: adhaan::Coordinates) -> f64 {
+    let (phi_1, phi_2) = (
+        from.latitude.to_radians(),
+        KAABA_COORDINATES.latitude.to_radians(),
+    );
+    let delta_lambda = (KAABA_COORDINATES.longitude - from.longitude).to_radians();
+    (delta_lambda.sin() * phi_2.cos())
+        .atan2(phi_1.cos() * phi_2.sin() - phi_1.sin() * phi_2.cos() * delta_lambda.cos())
+        .to_degrees()
+        .rem_euclid(360.0)
+}
+
+/// By the haversine formula, which stays accurate for short distances.
+pub fn distance(from: adhaan::Coordinates) -> f64 {
+    let (phi_1, phi_2) = (
+        from.latitude.to_radians(),
+        KAABA_COORDINATES.latitude.to_radians(),
+    );
+    let delta_phi = phi_2 - phi_1;
+    let delta_lambda = (KAABA_COORDINATES.longitude - from.longitude).to_radians();
+    let a = (delta_phi / 2.0).sin().powi(2)
+        + phi_1.cos() * phi_2.cos() * (delta_lambda / 2.0).sin().powi(2);
+    2.0 * EARTH_RADIUS * a.sqrt().asin()
+}
+
+/// Times on the local day of `date` the sun, above the horizon, stands at
+/// `azimuth`.
+fn sun_at_azimuth(
+    date: NaiveDate,
+    coordinates: adhaan::Coordinates,
+    azimuth: f64,
+) -> Vec<DateTime<Utc>> {
+    const STEP_MINUTES: i64 = 10;
+
+    // Signed angle from `azimuth` to the sun, within ±180°.
+    let offset_at = |time| {
+        let sun = astro::sun_horizontal(time, coordinates);
+        (
+            (sun.azimuth - azimuth + 180.0).rem_euclid(360.0) - 180.0,
+            sun.altitude,
+        )
+    };
+
+    let from = astro::local_midnight(date, coordinates);
+    let mut times = vec![];
+    let mut previous_time = from;
+    let mut previous = offset_at(from).0;
+    for step in 1..=(24 * 60 / STEP_MINUTES) {
+        let time = from + Duration::minutes(step * STEP_MINUTES);
+        let (current, _) = offset_at(time);
+
+        // Wrapping around behind the sun is not a crossing.
+        if previous.signum() != current.signum() && (previous - current).abs() < 180.0 {
+            let (mut low, mut high) = (previous_time, time);
+            for _ in 0..12 {
+                let mid = low + (high - low) / 2;
+                if offset_at(mid).0.signum() == previous.signum() {
+                    low = mid;
+                } else {
+                    high = mid;
+                }
+            }
+            if offset_at(high).1 > astro::HORIZON {
+                times.push(high);
+            }
+        }
+
+        previous_time = time;
+        previous = current;
+    }
+    times
+}
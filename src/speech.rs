@@ -0,0 +1,68 @@
+//! Spoken announcements, through SAPI on Windows and speech-dispatcher on Linux.
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Announcements being spoken.
+static SPEAKING: AtomicUsize = AtomicUsize::new(0);
+
+/// Speaks `text` on a thread of its own, as speaking blocks until it is done.
+pub fn speak(text: &str) {
+    let text = text.to_string();
+    SPEAKING.fetch_add(1, Ordering::SeqCst);
+    std::thread::spawn(move || {
+        if let Err(e) = platform::speak(&text) {
+            tracing::error!("Failed to speak: {:?}", e);
+        }
+        SPEAKING.fetch_sub(1, Ordering::SeqCst);
+    });
+}
+
+pub fn is_speaking() -> bool {
+    SPEAKING.load(Ordering::SeqCst) > 0
+}
+
+#[cfg(windows)]
+mod platform {
+    use windows::{
+        core::HSTRING,
+        Win32::{
+            Media::Speech::{ISpVoice, SpVoice, SPF_DEFAULT},
+            System::Com::{CoCreateInstance, CoInitializeEx, CLSCTX_ALL, COINIT_MULTITHREADED},
+        },
+    };
+
+    /// SAPI blocks until it is done.
+    pub fn speak(text: &str) -> anyhow::Result<()> {
+        unsafe {
+            CoInitializeEx(None, COINIT_MULTITHREADED)?;
+            let voice: ISpVoice = CoCreateInstance(&SpVoice, None, CLSCTX_ALL)?;
+            voice.Speak(
+                &HSTRING::from(text),
+                SPF_DEFAULT.0 as _,
+                std::ptr::null_mut(),
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod platform {
+    /// `spd-say` starts the speech-dispatcher daemon when it is not running,
+    /// and waits for the text to be spoken.
+    pub fn speak(text: &str) -> anyhow::Result<()> {
+        let status = std::process::Command::new("spd-say")
+            .args(["--wait", "--application-name", "Adhaan", "--", text])
+            .status()?;
+        anyhow::ensure!(status.success(), "spd-say failed with {}", status);
+        Ok(())
+    }
+}
+
+#[cfg(not(any(windows, target_os = "linux")))]
+mod platform {
+    pub fn speak(text: &str) -> anyhow::Result<()> {
+        tracing::info!("Speaking: {}", text);
+        Ok(())
+    }
+}
@@ -0,0 +1,168 @@
+//! The shown day from midnight to midnight, as laid out on the timeline of the
+//! main window.
+
+use adhaan::Prayer;
+use chrono::{DateTime, Duration, TimeZone, Utc};
+
+use crate::{app_state::AppState, config::DEFAULT_ISHRAQ_VALUE, utils::local_midnight};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Kind {
+    Waqt,
+    Forbidden,
+    Ishraq,
+    /// The first, middle or last third of a night, counted from zero.
+    NightThird(u8),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Lane {
+    Waqts,
+    Windows,
+    Night,
+}
+
+impl Kind {
+    pub fn lane(self) -> Lane {
+        match self {
+            Kind::Waqt => Lane::Waqts,
+            Kind::Forbidden | Kind::Ishraq => Lane::Windows,
+            Kind::NightThird(_) => Lane::Night,
+        }
+    }
+}
+
+/// A span on the timeline. It may start before or end after the day, and is
+/// cut at midnight when painted.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Segment {
+    pub kind: Kind,
+    pub label: &'static str,
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Timeline {
+    /// Midnight starting the day.
+    pub start: DateTime<Utc>,
+    /// Midnight ending the day.
+    pub end: DateTime<Utc>,
+    pub segments: Vec<Segment>,
+}
+
+impl Default for Timeline {
+    /// An empty day, until one is laid out.
+    fn default() -> Self {
+        let start = Utc.timestamp(0, 0);
+        Timeline {
+            start,
+            end: start + Duration::days(1),
+            segments: vec![],
+        }
+    }
+}
+
+impl Timeline {
+    /// The timeline of the day `data` is calculated for, from the same waqts
+    /// as the rows of the main window. Lays out two days of prayer times, so is
+    /// kept by `AppState::refresh_shown` rather than made on every update.
+    pub fn of(data: &AppState) -> Timeline {
+        use Prayer::*;
+
+        let start = local_midnight(data.date);
+        let end = local_midnight(data.date.succ());
+        let time_of = |prayer| data.prayers.time_of(prayer).unwrap();
+        // The night before runs into the morning.
+        let yesterday = data.on_date(data.date.pred());
+
+        let mut segments: Vec<_> = yesterday
+            .waqts()
+            .into_iter()
+            .chain(data.waqts())
+            .map(|waqt| Segment {
+                kind: Kind::Waqt,
+                label: waqt.label,
+                start: waqt.start,
+                end: waqt.end,
+            })
+            .collect();
+
+        // Forbidden windows are kept even when Ishraq is not.
+        let (after_sunrise, zawal) = data.config.ishraq.unwrap_or(DEFAULT_ISHRAQ_VALUE);
+        let (after_sunrise, zawal) = (
+            Duration::minutes(after_sunrise as _),
+            Duration::minutes(zawal as _),
+        );
+        let sunrise = data.adjusted_time_of(Sunrise);
+        let sunset = data.adjusted_time_of(Maghrib);
+        let forbidden = |label, start, end| Segment {
+            kind: Kind::Forbidden,
+            label,
+            start,
+            end,
+        };
+        segments.extend([
+            forbidden("Sunrise", sunrise, sunrise + after_sunrise),
+            forbidden("Zawal", time_of(Dhuhr) - zawal, time_of(Dhuhr)),
+            forbidden("Sunset", sunset - after_sunrise, sunset),
+        ]);
+        if data.config.ishraq.is_some() {
+            segments.push(Segment {
+                kind: Kind::Ishraq,
+                label: "Ishraq",
+                start: sunrise + after_sunrise,
+                end: time_of(Dhuhr) - zawal,
+            });
+        }
+
+        segments.extend(night_thirds(&yesterday));
+        segments.extend(night_thirds(data));
+
+        segments.retain(|segment| segment.end > start && segment.start < end);
+
+        Timeline {
+            start,
+            end,
+            segments,
+        }
+    }
+
+    /// Fraction of the day passed at `time`, within `0..=1`.
+    pub fn fraction_at(&self, time: DateTime<Utc>) -> f64 {
+        let elapsed = (time - self.start).num_seconds() as f64;
+        let length = (self.end - self.start).num_seconds() as f64;
+        (elapsed / length).clamp(0.0, 1.0)
+    }
+
+    /// The time a `fraction` of the day has passed.
+    pub fn time_at(&self, fraction: f64) -> DateTime<Utc> {
+        let length = (self.end - self.start).num_seconds() as f64;
+        self.start + Duration::seconds((length * fraction.clamp(0.0, 1.0)) as _)
+    }
+
+    pub fn segments_at(&self, time: DateTime<Utc>) -> impl Iterator<Item = &Segment> {
+        self.segments
+            .iter()
+            .filter(move |segment| (segment.start..segment.end).contains(&time))
+    }
+}
+
+/// The night from Maghrib of the day of `data` to the next Fajr, in thirds.
+fn night_thirds(data: &AppState) -> impl Iterator<Item = Segment> {
+    let dusk = data.prayers.time_of(Prayer::Maghrib).unwrap();
+    let third = (data.prayers.time_of(Prayer::Tomorrow).unwrap() - dusk) / 3;
+    [
+        "First third of the night",
+        "Middle third of the night",
+        "Last third of the night",
+    ]
+    .into_iter()
+    .enumerate()
+    .map(move |(index, label)| Segment {
+        kind: Kind::NightThird(index as _),
+        label,
+        start: dusk + third * index as i32,
+        end: dusk + third * (index as i32 + 1),
+    })
+}
@@ -0,0 +1,119 @@
+//! Prayer times of days other than today, for the timetable and exports.
+
+use adhaan::Prayer;
+use chrono::{DateTime, Datelike, Duration, NaiveDate, Utc};
+
+use crate::{app_state::AppState, config::AsrConfig, hijri::HijriDate, utils::DataWrapper};
+
+/// All the times of a day, with user adjustments applied.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Day {
+    pub date: NaiveDate,
+    /// The Hijri date during the daytime.
+    pub hijri: HijriDate,
+    pub fajr: DateTime<Utc>,
+    pub sunrise: DateTime<Utc>,
+    pub ishraq: Option<(DateTime<Utc>, DateTime<Utc>)>,
+    pub dhuhr: DateTime<Utc>,
+    pub asr_awwal: DateTime<Utc>,
+    pub asr_thaani: DateTime<Utc>,
+    pub maghrib: DateTime<Utc>,
+    pub isha: DateTime<Utc>,
+    pub qiyam: DateTime<Utc>,
+}
+
+impl Day {
+    pub fn of(data: &AppState, date: NaiveDate) -> Day {
+        let day = data.on_date(date);
+        let ishraq = day.config.ishraq.map(|(after_sunrise, zawal)| {
+            (
+                day.adjusted_time_of(Prayer::Sunrise) + Duration::minutes(after_sunrise as _),
+                day.prayers.time_of(Prayer::Dhuhr).unwrap() - Duration::minutes(zawal as _),
+            )
+        });
+
+        Day {
+            date,
+            hijri: day.hijri_date_on(date),
+            fajr: day.adjusted_time_of(Prayer::Fajr),
+            sunrise: day.adjusted_time_of(Prayer::Sunrise),
+            ishraq,
+            dhuhr: day.adjusted_time_of(Prayer::Dhuhr),
+            asr_awwal: day.adjusted_time_of(Prayer::AsrAwwal),
+            asr_thaani: day.adjusted_time_of(Prayer::AsrThaani),
+            maghrib: day.adjusted_time_of(Prayer::Maghrib),
+            isha: day.adjusted_time_of(Prayer::Isha),
+            qiyam: day.adjusted_time_of(Prayer::Qiyam),
+        }
+    }
+
+    /// The start of Asr as configured.
+    pub fn asr(&self, asr: AsrConfig) -> DateTime<Utc> {
+        match asr {
+            AsrConfig::AsrStartsAtAsrThaani(_) => self.asr_thaani,
+            _ => self.asr_awwal,
+        }
+    }
+}
+
+/// A day of the timetable, whether it is today and the Asr configuration.
+pub type Row = DataWrapper<(Day, bool, AsrConfig)>;
+
+/// The days from `from` to `to`, both included.
+pub fn days(data: &AppState, from: NaiveDate, to: NaiveDate) -> Vec<Day> {
+    let mut days = vec![];
+    let mut date = from;
+    while date <= to {
+        days.push(Day::of(data, date));
+        date = date.succ();
+    }
+    days
+}
+
+/// The days of the month `date` falls in.
+pub fn month_of(data: &AppState, date: NaiveDate) -> Vec<Day> {
+    let (first, last) = month_bounds(date);
+    days(data, first, last)
+}
+
+/// The first and last days of the month `date` falls in.
+pub fn month_bounds(date: NaiveDate) -> (NaiveDate, NaiveDate) {
+    let first = date.with_day(1).unwrap();
+    (first, next_month(first).pred())
+}
+
+/// The first day of the month after the one of `date`.
+pub fn next_month(date: NaiveDate) -> NaiveDate {
+    match date.month() {
+        12 => NaiveDate::from_ymd(date.year() + 1, 1, 1),
+        month => NaiveDate::from_ymd(date.year(), month + 1, 1),
+    }
+}
+
+/// The first day of the month before the one of `date`.
+pub fn previous_month(date: NaiveDate) -> NaiveDate {
+    date.with_day(1).unwrap().pred().with_day(1).unwrap()
+}
+
+/// The Hijri months a span of days from `first` to `last` falls in, as in
+/// "Sha'ban – Ramadan 1445 AH", or a single month when it falls in one.
+pub fn hijri_span(first: HijriDate, last: HijriDate) -> String {
+    if first.month == last.month && first.year == last.year {
+        format!("{} {} AH", first.month_name(), first.year)
+    } else if first.year == last.year {
+        format!(
+            "{} – {} {} AH",
+            first.month_name(),
+            last.month_name(),
+            last.year
+        )
+    } else {
+        format!(
+            "{} {} – {} {} AH",
+            first.month_name(),
+            first.year,
+            last.month_name(),
+            last.year
+        )
+    }
+}
@@ -0,0 +1,111 @@
+use chrono::{DateTime, Local, Utc};
+use druid::{
+    lens, theme,
+    widget::{prelude::*, Either, Flex, Label, Maybe, SizedBox, TextBox},
+    WidgetExt, WindowDesc, WindowState,
+};
+
+use crate::{
+    alarm::{self, Alarm},
+    utils::localized_label,
+    widgets::button::Button,
+    *,
+};
+
+fn format_time(time: DateTime<Utc>) -> String {
+    time.with_timezone(&Local).format("%I:%M %p").to_string()
+}
+
+/// A window over everything else, so that the alarm cannot be missed.
+pub fn window() -> WindowDesc<AppState> {
+    WindowDesc::new(alarm_root())
+        .title("Fajr alarm")
+        .show_titlebar(false)
+        .set_always_on_top(true)
+        .resizable(false)
+        .set_window_state(WindowState::Maximized)
+}
+
+fn alarm_root() -> impl Widget<AppState> {
+    let times = Label::new(|data: &AppState, _: &Env| {
+        format!(
+            "Fajr at {}, sunrise at {}",
+            format_time(data.adjusted_time_of(Prayer::Fajr)),
+            format_time(data.adjusted_time_of(Prayer::Sunrise)),
+        )
+    })
+    .with_text_size(24.0)
+    .with_text_color(theme::FOREGROUND_DARK);
+
+    let puzzle = Maybe::or_empty(|| {
+        Flex::row()
+            .with_child(
+                Label::new(|alarm: &Alarm, _: &Env| {
+                    let (a, b) = alarm.puzzle.unwrap_or_default();
+                    format!("{} + {} =", a, b)
+                })
+                .with_text_size(32.0),
+            )
+            .with_default_spacer()
+            .with_child(
+                TextBox::new()
+                    .with_text_size(32.0)
+                    .fix_width(96.0)
+                    .lens(lens!(Alarm, answer)),
+            )
+    })
+    .lens(lens!(AppState, alarm));
+    let puzzle = Either::new(
+        |data: &AppState, _: &Env| {
+            data.alarm
+                .as_ref()
+                .map_or(false, |alarm| alarm.puzzle.is_some())
+        },
+        puzzle,
+        SizedBox::empty(),
+    );
+
+    let snooze = Either::new(
+        |data: &AppState, _: &Env| alarm::snooze_until(data, Utc::now()).is_some(),
+        Button::new(|data: &AppState, _: &Env| {
+            format!("Snooze {} minutes", data.config.fajr_alarm.snooze)
+        })
+        .on_click(|ctx, data: &mut AppState, _| {
+            // Sunrise may have come closer since the button was shown.
+            if let Some(until) = alarm::snooze_until(data, Utc::now()) {
+                data.alarm_snoozed_until = Some(until);
+                data.alarm = None;
+                audio::stop();
+                ctx.window().close();
+            }
+        }),
+        SizedBox::empty(),
+    );
+    let dismiss =
+        Button::new(localized_label("Dismiss")).on_click(|ctx, data: &mut AppState, _| {
+            if data.alarm.as_ref().map_or(true, Alarm::can_dismiss) {
+                data.alarm = None;
+                audio::stop();
+                ctx.window().close();
+            }
+        });
+
+    Flex::column()
+        .with_flex_spacer(1.0)
+        .with_child(Label::new(localized_label("Time for Fajr")).with_text_size(64.0))
+        .with_default_spacer()
+        .with_child(times)
+        .with_spacer(32.0)
+        .with_child(puzzle)
+        .with_spacer(32.0)
+        .with_child(
+            Flex::row()
+                .with_child(snooze)
+                .with_spacer(32.0)
+                .with_child(dismiss),
+        )
+        .with_flex_spacer(1.0)
+        .center()
+        .padding(16.0)
+        .env_scope(|env, app_state| app_state.config.apply_appearance_to_env(env))
+}
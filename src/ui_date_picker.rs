@@ -0,0 +1,95 @@
+use chrono::{Datelike, Duration, NaiveDate, Weekday};
+use druid::{
+    theme,
+    widget::{prelude::*, Flex, Label},
+    WidgetExt,
+};
+
+use crate::{timetable, utils::localized_label, widgets::button::Button, *};
+
+pub const WINDOW_SIZE: Size = Size::new(320.0, 340.0);
+
+const CELL_SIZE: f64 = 32.0;
+
+/// The day shown in `cell` of the grid of the month of `month`, which starts
+/// on the Monday on or before the first.
+fn date_at(month: NaiveDate, cell: usize) -> NaiveDate {
+    let first = month.with_day(1).unwrap();
+    let start = first - Duration::days(first.weekday().num_days_from_monday() as _);
+    start + Duration::days(cell as _)
+}
+
+fn day_cell(cell: usize) -> impl Widget<AppState> {
+    Label::new(move |data: &AppState, _: &Env| date_at(data.picker_month, cell).day().to_string())
+        .center()
+        .fix_size(CELL_SIZE, CELL_SIZE)
+        .env_scope(move |env, data: &AppState| {
+            let date = date_at(data.picker_month, cell);
+            if date.month() != data.picker_month.month() {
+                env.set(theme::TEXT_COLOR, env.get(theme::FOREGROUND_DARK));
+            }
+            if date == data.shown_date() {
+                env.set(theme::TEXT_COLOR, env.get(theme::PRIMARY_LIGHT));
+            }
+        })
+        .on_click(move |ctx, data: &mut AppState, _| {
+            data.view(date_at(data.picker_month, cell));
+            ctx.window().close();
+        })
+}
+
+pub fn date_picker_root() -> impl Widget<AppState> {
+    let navigation = Flex::row()
+        .with_child(Button::new("<").on_click(|_, data: &mut AppState, _| {
+            data.picker_month = timetable::previous_month(data.picker_month)
+        }))
+        .with_flex_child(
+            Label::new(|data: &AppState, _: &Env| data.picker_month.format("%B %Y").to_string())
+                .with_text_size(15.0)
+                .center(),
+            1.0,
+        )
+        .with_child(Button::new(">").on_click(|_, data: &mut AppState, _| {
+            data.picker_month = timetable::next_month(data.picker_month)
+        }));
+
+    let mut weekdays = Flex::row();
+    let mut weekday = Weekday::Mon;
+    for _ in 0..7 {
+        weekdays.add_child(
+            Label::new(weekday.to_string())
+                .with_text_color(theme::FOREGROUND_DARK)
+                .center()
+                .fix_width(CELL_SIZE),
+        );
+        weekday = weekday.succ();
+    }
+
+    let mut grid = Flex::column().with_child(weekdays);
+    for week in 0..6 {
+        let mut row = Flex::row();
+        for day in 0..7 {
+            row.add_child(day_cell(week * 7 + day));
+        }
+        grid.add_child(row);
+    }
+
+    Flex::column()
+        .with_child(navigation)
+        .with_default_spacer()
+        .with_child(grid)
+        .with_default_spacer()
+        .with_child(Button::new(localized_label("Today")).on_click(
+            |ctx, data: &mut AppState, _| {
+                data.view(data.date);
+                ctx.window().close();
+            },
+        ))
+        .center()
+        .padding(16.0)
+        .controller(ui_main::RootController)
+        .env_scope(|env, app_state| {
+            app_state.config.apply_appearance_to_env(env);
+            env.set(theme::UI_FONT, env.get(theme::UI_FONT).with_size(12.0))
+        })
+}
@@ -2,14 +2,12 @@ use chrono::prelude::*;
 use druid::{
     lens, theme,
     widget::{self, prelude::*, CrossAxisAlignment, Flex, FlexParams, Label},
-    Command, FontWeight, HasRawWindowHandle, LocalizedString, RawWindowHandle, Scalable, Target,
-    WidgetExt, WindowDesc, WindowId, WindowLevel,
+    Command, FontWeight, LocalizedString, Point, Scalable, Target, TimerToken, WidgetExt,
+    WindowDesc, WindowHandle, WindowId, WindowLevel,
 };
-use extract::*;
-
-use winapi::um::winuser;
 
 use crate::{
+    timeline::{Kind, Lane, Timeline},
     utils::{lens_map_get, DataWrapper},
     widgets::button::Button,
     *,
@@ -24,24 +22,30 @@ pub mod color {
         REMAINING: Color,
         CLOSE_HOT: Color,
         CLOSE_ACTIVE: Color,
+        MOON_LIT: Color,
     }
 }
 
 pub mod size {
     use druid::Size;
 
-    pub const MAIN_WINDOW: Size = Size::new(320.0, 400.0);
+    pub const MAIN_WINDOW: Size = Size::new(320.0, 516.0);
     pub const CORNER_BUFFER: Size = Size::new(16.0, 16.0);
     pub const ACTIVE_CORNER_RADIUS: f64 = 8.0;
+    pub const MOON_INDICATOR: f64 = 20.0;
+    pub const DONE_MARK: f64 = 20.0;
+    pub const TIMELINE_HEIGHT: f64 = 30.0;
 }
 
 pub mod selector {
     druid_widget_nursery::selectors! {
         INIT,
         SHOW,
+        HIDE,
     }
 }
 
+#[cfg(windows)]
 pub static TRAY_ICON: &[u8] = include_bytes!("../resources/icon.ico");
 
 pub fn main_root() -> impl Widget<AppState> {
@@ -56,14 +60,43 @@ pub fn main_root() -> impl Widget<AppState> {
                 Label::new(|location: &String, _: &Env| location.clone()).with_text_size(24.0),
             )
     })
-    .lens(lens!(AppState, config.location_name));
+    .lens(lens!(AppState, config.location_name))
+    .on_click(open_qibla);
 
     let title = Flex::row()
         .with_child(
             Label::new(LocalizedString::new("Adhaan").with_placeholder("Adhaan"))
                 .with_text_size(24.0),
         )
-        .with_child(location_name);
+        .with_child(location_name)
+        .with_default_spacer()
+        .with_child(
+            moon_painter()
+                .fix_size(size::MOON_INDICATOR, size::MOON_INDICATOR)
+                .lens(AppState::lens_moon_phase())
+                .on_click(|ctx, _, _| {
+                    ctx.new_window(
+                        WindowDesc::new(ui_moon::moon_root())
+                            .set_level(WindowLevel::Modal(ctx.window().clone()))
+                            .show_titlebar(false)
+                            .show_in_taskbar(false)
+                            .set_always_on_top(true)
+                            .resizable(false)
+                            .window_size(ui_main::size::MAIN_WINDOW),
+                    )
+                }),
+        );
+
+    let hijri_date = Label::new(|date: &String, _: &Env| date.clone())
+        .with_text_size(15.0)
+        .with_text_color(theme::FOREGROUND_DARK)
+        .lens(AppState::lens_hijri_date());
+    let upcoming_event = widget::Maybe::or_empty(|| {
+        Label::new(|event: &String, _: &Env| event.clone())
+            .with_text_size(13.0)
+            .with_text_color(theme::FOREGROUND_DARK)
+    })
+    .lens(AppState::lens_upcoming_event());
 
     let asr_row = widget::Either::<AsrLensed>::new(
         |(DataWrapper((_, asr_2)), _): &AsrLensed, _: &Env| asr_2.is_some(),
@@ -85,15 +118,17 @@ pub fn main_root() -> impl Widget<AppState> {
     .background(background_painter())
     .lens(AppState::lens_asr());
 
-    let now_remaining = Label::new(|(data, _): &(String, bool), _: &Env| data.clone())
-        .with_text_size(15.0)
-        .env_scope(|env, (_, critical)| {
-            if *critical {
-                env.set(theme::TEXT_COLOR, env.get(color::CLOSE_ACTIVE))
-            }
-        })
-        .lens(AppState::lens_remaining())
-        .controller(RemainingTimeController);
+    let now_remaining = Label::new(
+        |(remaining, _): &(DataWrapper<Remaining>, bool), env: &Env| remaining.0.localized(env),
+    )
+    .with_text_size(15.0)
+    .env_scope(|env, (_, critical)| {
+        if *critical {
+            env.set(theme::TEXT_COLOR, env.get(color::CLOSE_ACTIVE))
+        }
+    })
+    .lens(AppState::lens_remaining())
+    .controller(RemainingTimeController);
 
     let buttons = Flex::<AppState>::row()
         .with_child(
@@ -128,6 +163,70 @@ pub fn main_root() -> impl Widget<AppState> {
             }),
         )
         .with_flex_spacer(1.0)
+        .with_child(widget::Either::new(
+            |data: &AppState, _: &Env| data.playing,
+            Button::new(utils::localized_label("Stop adhan")).on_click(
+                |_, data: &mut AppState, _| {
+                    audio::stop();
+                    data.playing = false;
+                },
+            ),
+            widget::SizedBox::empty(),
+        ))
+        .with_flex_spacer(1.0)
+        .with_child(
+            Button::from_label(
+                utils::Icon::Compass
+                    .label(FontWeight::REGULAR)
+                    .with_text_size(16.0),
+            )
+            .on_click(open_qibla),
+        )
+        .with_default_spacer()
+        .with_child(
+            Button::from_label(
+                utils::Icon::Calendar
+                    .label(FontWeight::REGULAR)
+                    .with_text_size(16.0),
+            )
+            .on_click(|ctx, data: &mut AppState, _| {
+                data.timetable_month = data.shown_date();
+                ctx.new_window(
+                    WindowDesc::new(ui_timetable::timetable_root())
+                        .set_level(WindowLevel::Modal(ctx.window().clone()))
+                        .show_titlebar(false)
+                        .show_in_taskbar(false)
+                        .set_always_on_top(true)
+                        .resizable(false)
+                        .window_size(ui_timetable::WINDOW_SIZE),
+                )
+            }),
+        )
+        .with_default_spacer()
+        .with_child(
+            Button::from_label(
+                utils::Icon::Travel
+                    .label(FontWeight::REGULAR)
+                    .with_text_size(16.0),
+            )
+            .env_scope(|env, data: &AppState| {
+                if data.config.travel.enabled {
+                    env.set(theme::TEXT_COLOR, env.get(theme::PRIMARY_LIGHT));
+                }
+            })
+            .on_click(|ctx, data: &mut AppState, _| {
+                let enabled = !data.config.travel.enabled;
+                data.config
+                    .travel
+                    .set_enabled(enabled, Local::today().naive_local());
+                if let Err(e) = data.config.save() {
+                    tracing::error!("Failed to save config: {:?}", e);
+                }
+                data.refresh_shown();
+                ctx.submit_command(feed::APPLY);
+            }),
+        )
+        .with_default_spacer()
         .with_child(
             Button::from_label(
                 utils::Icon::Settings
@@ -147,39 +246,152 @@ pub fn main_root() -> impl Widget<AppState> {
             }),
         );
 
-    Flex::column()
-        .with_flex_spacer(1.0)
-        .with_child(title)
-        .with_default_spacer()
-        .with_child(waqt_row(Prayer::Fajr))
+    let day_navigation = Flex::row()
         .with_child(
-            prayer_row()
-                .background(background_painter())
-                .lens(AppState::lens_sunrise()),
+            Button::new("<")
+                .on_click(|_, data: &mut AppState, _| data.view(data.shown_date().pred())),
+        )
+        .with_flex_child(
+            Label::new(|data: &AppState, _: &Env| match data.viewing {
+                Some(date) => format!("{} · {}", date.format("%a %d %b"), data.hijri_date_on(date)),
+                None => data.date.format("%A %d %B").to_string(),
+            })
+            .with_text_color(theme::FOREGROUND_DARK)
+            .center()
+            .on_click(|ctx, data: &mut AppState, _| {
+                data.picker_month = data.shown_date();
+                ctx.new_window(
+                    WindowDesc::new(ui_date_picker::date_picker_root())
+                        .set_level(WindowLevel::Modal(ctx.window().clone()))
+                        .show_titlebar(false)
+                        .show_in_taskbar(false)
+                        .set_always_on_top(true)
+                        .resizable(false)
+                        .window_size(ui_date_picker::WINDOW_SIZE),
+                )
+            }),
+            1.0,
         )
+        .with_child(widget::Either::new(
+            |data: &AppState, _: &Env| data.viewing.is_some(),
+            Button::new(utils::localized_label("Today"))
+                .on_click(|_, data: &mut AppState, _| data.view(data.date)),
+            widget::SizedBox::empty(),
+        ))
         .with_child(
+            Button::new(">")
+                .on_click(|_, data: &mut AppState, _| data.view(data.shown_date().succ())),
+        );
+
+    let rows = Flex::column()
+        .with_child(waqt_row(Prayer::Fajr))
+        .with_child(prayable(
             prayer_row()
                 .background(background_painter())
-                .lens(AppState::lens_dhuhr()),
-        )
-        .with_child(asr_row)
-        .with_child(waqt_row(Prayer::Maghrib))
-        .with_child(waqt_row(Prayer::Isha))
+                .lens(AppState::lens_sunrise()),
+            None,
+        ))
+        .with_child(widget::Either::new(
+            |data: &AppState, _: &Env| data.config.travel.enabled,
+            Flex::column()
+                .with_child(combined_row(Prayer::Dhuhr))
+                .with_child(combined_row(Prayer::Maghrib)),
+            Flex::column()
+                .with_child(prayable(
+                    prayer_row()
+                        .background(background_painter())
+                        .lens(AppState::lens_dhuhr()),
+                    Some(Prayer::Dhuhr),
+                ))
+                .with_child(prayable(asr_row, Some(Prayer::AsrAwwal)))
+                .with_child(waqt_row(Prayer::Maghrib))
+                .with_child(waqt_row(Prayer::Isha)),
+        ))
         .with_child(waqt_row(Prayer::Qiyam))
         .with_default_spacer()
+        .with_child(DayTimeline::default().lens(AppState::lens_timeline()))
+        .lens(AppState::lens_shown());
+
+    Flex::column()
+        .with_flex_spacer(1.0)
+        .with_child(title)
+        .with_child(hijri_date)
+        .with_child(upcoming_event)
+        .with_default_spacer()
+        .with_child(day_navigation)
+        .with_child(rows)
+        .with_default_spacer()
         .with_child(now_remaining)
         .with_flex_spacer(1.0)
         .with_child(buttons)
         .center()
         .padding(16.0)
         .controller(RootController)
+        .controller(alerts::AlertController::new())
+        .controller(feed::FeedController)
         .env_scope(|env, app_state| app_state.config.apply_appearance_to_env(env))
 }
 
 fn waqt_row(prayer: Prayer) -> impl Widget<AppState> {
-    prayer_row()
-        .background(background_painter())
-        .lens(AppState::lens_prayer(prayer))
+    prayable(
+        prayer_row()
+            .background(background_painter())
+            .lens(AppState::lens_prayer(prayer)),
+        Some(prayer),
+    )
+}
+
+fn combined_row(prayer: Prayer) -> impl Widget<AppState> {
+    prayable(
+        prayer_row()
+            .background(background_painter())
+            .lens(AppState::lens_combined(prayer)),
+        Some(prayer),
+    )
+}
+
+/// Opens the Qibla window for the shown day.
+fn open_qibla(ctx: &mut EventCtx, data: &mut AppState, _: &Env) {
+    data.refresh_qibla();
+    ctx.new_window(
+        WindowDesc::new(ui_qibla::qibla_root())
+            .set_level(WindowLevel::Modal(ctx.window().clone()))
+            .show_titlebar(false)
+            .show_in_taskbar(false)
+            .set_always_on_top(true)
+            .resizable(false)
+            .window_size(size::MAIN_WINDOW),
+    )
+}
+
+/// Adds a mark to `row` showing whether `prayer` is prayed, toggled by clicking
+/// the mark. Rows without a prayer only get the space for alignment.
+fn prayable(row: impl Widget<AppState> + 'static, prayer: Option<Prayer>) -> impl Widget<AppState> {
+    let mark: Box<dyn Widget<AppState>> = match prayer {
+        Some(prayer) => widget::Either::new(
+            move |data: &AppState, _: &Env| data.is_prayed(prayer),
+            utils::Icon::Done
+                .label(FontWeight::REGULAR)
+                .with_text_color(theme::FOREGROUND_DARK),
+            // Faint while unmarked, to show where to click.
+            utils::Icon::Done
+                .label(FontWeight::REGULAR)
+                .with_text_color(theme::BORDER_DARK),
+        )
+        .on_click(move |_, data: &mut AppState, _| {
+            // Only today's prayers can be marked.
+            if data.viewing.is_none() {
+                let prayed = data.is_prayed(prayer);
+                data.set_prayed(prayer, !prayed);
+            }
+        })
+        .boxed(),
+        None => widget::SizedBox::empty().boxed(),
+    };
+
+    Flex::row()
+        .with_flex_child(row, 1.0)
+        .with_child(mark.fix_width(size::DONE_MARK))
 }
 
 fn prayer_row() -> impl Widget<PrayerLensed> {
@@ -207,6 +419,51 @@ fn prayer_row() -> impl Widget<PrayerLensed> {
         .padding(2.0)
 }
 
+/// Paints the moon with the illuminated fraction and waxing state given.
+pub fn moon_painter() -> widget::Painter<(f64, bool)> {
+    use std::f64::consts::{FRAC_PI_2, PI};
+
+    widget::Painter::new(|ctx, &(illumination, waxing): &(f64, bool), env| {
+        let size = ctx.size();
+        let center = size.to_rect().center();
+        let radius = size.width.min(size.height) / 2.0 - 1.0;
+
+        ctx.fill(
+            kurbo::Circle::new(center, radius),
+            &env.get(theme::FOREGROUND_DARK).with_alpha(0.3),
+        );
+
+        let lit_side = if waxing { 1.0 } else { -1.0 };
+        let terminator_bulge = lit_side * (1.0 - 2.0 * illumination);
+
+        let mut lit = kurbo::BezPath::new();
+        lit.move_to((center.x, center.y - radius));
+        lit.extend(
+            kurbo::Arc {
+                center,
+                radii: (radius, radius).into(),
+                start_angle: -FRAC_PI_2,
+                sweep_angle: lit_side * PI,
+                x_rotation: 0.0,
+            }
+            .append_iter(0.1),
+        );
+        lit.extend(
+            kurbo::Arc {
+                center,
+                radii: (radius * terminator_bulge.abs(), radius).into(),
+                start_angle: FRAC_PI_2,
+                sweep_angle: if terminator_bulge > 0.0 { -PI } else { PI },
+                x_rotation: 0.0,
+            }
+            .append_iter(0.1),
+        );
+        lit.close_path();
+
+        ctx.fill(lit, &env.get(color::MOON_LIT));
+    })
+}
+
 fn background_painter<T>() -> widget::Painter<(T, Option<(f64, bool)>)> {
     widget::Painter::new(|ctx, data: &(T, Option<(f64, bool)>), env| {
         if let Some((elapsed_fraction, critical)) = data.1 {
@@ -256,6 +513,199 @@ fn background_painter<T>() -> widget::Painter<(T, Option<(f64, bool)>)> {
     })
 }
 
+/// The shown day from midnight to midnight, with waqts on top, forbidden and
+/// Ishraq windows below them and the thirds of the nights at the bottom.
+/// Hovering shows what falls at that time.
+#[derive(Default)]
+struct DayTimeline {
+    hovered_at: Option<Point>,
+    /// Moves the marker of now on the minute.
+    timer: Option<TimerToken>,
+}
+
+impl DayTimeline {
+    const WAQTS_LANE: f64 = 12.0;
+    const MINOR_LANE: f64 = 5.0;
+    const LANE_GAP: f64 = 1.0;
+    const TOOLTIP_PADDING: f64 = 4.0;
+
+    /// Top and bottom of `lane`.
+    fn lane_bounds(lane: Lane) -> (f64, f64) {
+        let top = match lane {
+            Lane::Waqts => return (0.0, Self::WAQTS_LANE),
+            Lane::Windows => Self::WAQTS_LANE + Self::LANE_GAP,
+            Lane::Night => Self::WAQTS_LANE + Self::MINOR_LANE + 2.0 * Self::LANE_GAP,
+        };
+        (top, top + Self::MINOR_LANE)
+    }
+
+    fn tooltip_text(timeline: &Timeline, at: DateTime<Utc>) -> String {
+        let format = |time: DateTime<Utc>| time.with_timezone(&Local).format("%I:%M %p");
+        let mut text = format(at).to_string();
+        for segment in timeline.segments_at(at) {
+            text += &format!(
+                "\n{}  {} – {}",
+                segment.label,
+                format(segment.start),
+                format(segment.end)
+            );
+        }
+        text
+    }
+}
+
+impl Widget<DataWrapper<Timeline>> for DayTimeline {
+    fn event(&mut self, ctx: &mut EventCtx, event: &Event, _: &mut DataWrapper<Timeline>, _: &Env) {
+        match event {
+            Event::MouseMove(mouse) => {
+                self.hovered_at = ctx.is_hot().then(|| mouse.pos);
+                ctx.request_paint();
+            }
+            Event::Timer(token) if Some(*token) == self.timer => {
+                self.timer = Some(ctx.request_timer(alerts::until_next_minute()));
+                ctx.request_paint();
+            }
+            _ => {}
+        }
+    }
+
+    fn lifecycle(
+        &mut self,
+        ctx: &mut LifeCycleCtx,
+        event: &LifeCycle,
+        _: &DataWrapper<Timeline>,
+        _: &Env,
+    ) {
+        match event {
+            LifeCycle::WidgetAdded => {
+                self.timer = Some(ctx.request_timer(alerts::until_next_minute()));
+            }
+            LifeCycle::HotChanged(false) => {
+                self.hovered_at = None;
+                ctx.request_paint();
+            }
+            _ => {}
+        }
+    }
+
+    fn update(
+        &mut self,
+        ctx: &mut UpdateCtx,
+        old_data: &DataWrapper<Timeline>,
+        data: &DataWrapper<Timeline>,
+        _: &Env,
+    ) {
+        if !old_data.same(data) {
+            ctx.request_paint();
+        }
+    }
+
+    fn layout(
+        &mut self,
+        _: &mut LayoutCtx,
+        bc: &BoxConstraints,
+        _: &DataWrapper<Timeline>,
+        _: &Env,
+    ) -> Size {
+        let width = if bc.is_width_bounded() {
+            bc.max().width
+        } else {
+            size::MAIN_WINDOW.width
+        };
+        bc.constrain(Size::new(width, size::TIMELINE_HEIGHT))
+    }
+
+    fn paint(&mut self, ctx: &mut PaintCtx, data: &DataWrapper<Timeline>, env: &Env) {
+        use druid::piet::{Text, TextLayout, TextLayoutBuilder};
+
+        let timeline = &data.0;
+        let width = ctx.size().width;
+        let x_of = |time| width * timeline.fraction_at(time);
+        let (_, bottom) = Self::lane_bounds(Lane::Night);
+
+        for lane in [Lane::Waqts, Lane::Windows, Lane::Night] {
+            let (top, bottom) = Self::lane_bounds(lane);
+            ctx.fill(
+                kurbo::Rect::new(0.0, top, width, bottom),
+                &env.get(theme::BACKGROUND_DARK),
+            );
+        }
+
+        for segment in &timeline.segments {
+            let (top, bottom) = Self::lane_bounds(segment.kind.lane());
+            let rect = kurbo::Rect::new(x_of(segment.start), top, x_of(segment.end), bottom);
+            let fill = match segment.kind {
+                Kind::Waqt => env.get(color::REMAINING),
+                Kind::Forbidden => env.get(color::ELAPSED_CRITICAL),
+                Kind::Ishraq => env.get(color::ELAPSED_OKAY),
+                Kind::NightThird(third) => env
+                    .get(theme::FOREGROUND_DARK)
+                    .with_alpha(0.3 + 0.25 * third as f64),
+            };
+            ctx.fill(rect, &fill);
+            if segment.kind == Kind::Waqt {
+                // Waqts follow each other in the same colour.
+                ctx.fill(
+                    kurbo::Rect::new(rect.x0, top, rect.x0 + 1.0, bottom),
+                    &env.get(theme::WINDOW_BACKGROUND_COLOR),
+                );
+            }
+        }
+
+        for hour in (0..=24).step_by(3) {
+            let x = x_of(timeline.start + chrono::Duration::hours(hour));
+            let length = if hour % 6 == 0 { 6.0 } else { 3.0 };
+            ctx.stroke(
+                kurbo::Line::new((x, bottom + 2.0), (x, bottom + 2.0 + length)),
+                &env.get(theme::FOREGROUND_DARK),
+                1.0,
+            );
+        }
+
+        // Only today has now in it.
+        let now = Utc::now();
+        if (timeline.start..timeline.end).contains(&now) {
+            let x = x_of(now);
+            let marker = env.get(theme::TEXT_COLOR);
+            ctx.stroke(kurbo::Line::new((x, -2.0), (x, bottom + 2.0)), &marker, 2.0);
+            ctx.fill(kurbo::Circle::new((x, -2.0), 3.0), &marker);
+        }
+
+        if let Some(at) = self.hovered_at {
+            let text = Self::tooltip_text(timeline, timeline.time_at(at.x / width));
+            let layout = ctx
+                .text()
+                .new_text_layout(text)
+                .font(env.get(theme::UI_FONT).family, 12.0)
+                .text_color(env.get(theme::TEXT_COLOR))
+                .build()
+                .unwrap();
+            let tooltip_size = layout.size() + Size::new(2.0, 2.0) * Self::TOOLTIP_PADDING;
+            // Above the timeline, over the rows, and kept inside the window.
+            let origin = Point::new(
+                (at.x - tooltip_size.width / 2.0).clamp(0.0, (width - tooltip_size.width).max(0.0)),
+                -tooltip_size.height - 6.0,
+            );
+            let rect = tooltip_size
+                .to_rect()
+                .with_origin(origin)
+                .to_rounded_rect(size::ACTIVE_CORNER_RADIUS / 2.0);
+            let (background, border) = (
+                env.get(theme::WINDOW_BACKGROUND_COLOR),
+                env.get(theme::FOREGROUND_DARK),
+            );
+            ctx.paint_with_z_index(1, move |ctx| {
+                ctx.fill(rect, &background);
+                ctx.stroke(rect, &border, 1.0);
+                ctx.draw_text(
+                    &layout,
+                    origin + (Self::TOOLTIP_PADDING, Self::TOOLTIP_PADDING),
+                );
+            });
+        }
+    }
+}
+
 pub struct AppDelegate(pub Option<WindowId>);
 
 impl druid::AppDelegate<AppState> for AppDelegate {
@@ -265,17 +715,14 @@ impl druid::AppDelegate<AppState> for AppDelegate {
         id: druid::WindowId,
         handle: druid::WindowHandle,
         focus: bool,
-        _: &mut AppState,
+        data: &mut AppState,
         _: &Env,
     ) {
         if !focus {
             if id == self.0.unwrap() {
-                let raw_handle =
-                    extract!(handle.raw_window_handle(), RawWindowHandle::Windows(h) => h);
-                unsafe {
-                    winuser::ShowWindow(std::mem::transmute(raw_handle.hwnd), winuser::SW_HIDE);
-                }
-            } else {
+                hide(&handle);
+            } else if data.alarm.is_none() {
+                // The alarm window stays until the alarm is dismissed or snoozed.
                 handle.close()
             }
         }
@@ -322,19 +769,53 @@ impl<W: Widget<AppState>> druid::widget::Controller<AppState, W> for RootControl
                         - size::CORNER_BUFFER.to_vec2(),
                 );
             }
-            Event::Command(c) if c.is(selector::SHOW) => unsafe {
-                let hwnd = {
-                    std::mem::transmute(extract!(ctx.window().raw_window_handle(), RawWindowHandle::Windows(h) => h).hwnd)
-                };
-                winuser::ShowWindow(hwnd, winuser::SW_SHOW);
-                winuser::SetForegroundWindow(hwnd);
-                winuser::SetActiveWindow(hwnd);
-            },
+            Event::Command(c) if c.is(selector::SHOW) => show(ctx.window()),
+            Event::Command(c) if c.is(selector::HIDE) => hide(ctx.window()),
             _ => child.event(ctx, event, data, env),
         }
     }
 }
 
+#[cfg(windows)]
+fn show(handle: &WindowHandle) {
+    use druid::{HasRawWindowHandle, RawWindowHandle};
+    use extract::*;
+    use winapi::um::winuser;
+
+    unsafe {
+        let hwnd = std::mem::transmute(
+            extract!(handle.raw_window_handle(), RawWindowHandle::Windows(h) => h).hwnd,
+        );
+        winuser::ShowWindow(hwnd, winuser::SW_SHOW);
+        winuser::SetForegroundWindow(hwnd);
+        winuser::SetActiveWindow(hwnd);
+    }
+}
+
+/// Hides the main window to the tray.
+#[cfg(windows)]
+fn hide(handle: &WindowHandle) {
+    use druid::{HasRawWindowHandle, RawWindowHandle};
+    use extract::*;
+    use winapi::um::winuser;
+
+    unsafe {
+        let hwnd = std::mem::transmute(
+            extract!(handle.raw_window_handle(), RawWindowHandle::Windows(h) => h).hwnd,
+        );
+        winuser::ShowWindow(hwnd, winuser::SW_HIDE);
+    }
+}
+
+#[cfg(not(windows))]
+fn show(handle: &WindowHandle) {
+    handle.bring_to_front_and_focus();
+}
+
+/// Without a tray to bring it back from, the main window stays where it is.
+#[cfg(not(windows))]
+fn hide(_: &WindowHandle) {}
+
 struct RemainingTimeController;
 
 impl<W: Widget<AppState>> widget::Controller<AppState, W> for RemainingTimeController {
@@ -358,15 +839,24 @@ impl<W: Widget<AppState>> widget::Controller<AppState, W> for RemainingTimeContr
                     ctx.request_timer(Duration::from_secs(60));
                 }
 
-                let now = Utc::now();
-                if now.hour() == 0 && now.minute() == 0 && now.second() == 0 {
-                    data.prayers = PrayerTimes::calculate(
-                        now.date().naive_utc(),
-                        data.config.coordinates,
-                        data.config.method.get_parameters(),
-                    )
-                    .unwrap();
+                data.refresh_moon();
+
+                if data.config.travel.expire(Local::today().naive_local()) {
+                    if let Err(e) = data.config.save() {
+                        tracing::error!("Failed to save config: {:?}", e);
+                    }
+                    ctx.submit_command(feed::APPLY);
+                }
+
+                let today = Local::today().naive_local();
+                if data.date != today {
+                    data.recalculate(today);
+                    if data.viewing == Some(today) {
+                        data.viewing = None;
+                    }
+                    hooks::run_day_rollover(data);
                 }
+                data.refresh_shown();
 
                 ctx.request_update();
             }
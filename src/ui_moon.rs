@@ -0,0 +1,140 @@
+use chrono::{DateTime, Duration, Local, Utc};
+use druid::{
+    theme,
+    widget::{prelude::*, CrossAxisAlignment, Flex, Label, List},
+    WidgetExt,
+};
+
+use crate::{
+    moon::{CrescentVisibility, MoonInfo},
+    utils::{lens_map_get, DataWrapper},
+    *,
+};
+
+fn format_time(time: Option<DateTime<Utc>>) -> String {
+    time.map_or_else(
+        || "-".into(),
+        |time| time.with_timezone(&Local).format("%I:%M %p").to_string(),
+    )
+}
+
+fn format_date_time(time: DateTime<Utc>) -> String {
+    time.with_timezone(&Local)
+        .format("%a %d %b, %I:%M %p")
+        .to_string()
+}
+
+fn format_age(age: Duration) -> String {
+    format!("{}d {}h", age.num_days(), age.num_hours() % 24)
+}
+
+fn detail_row(
+    label: &'static str,
+    value: impl Fn(&MoonInfo) -> String + 'static,
+) -> impl Widget<DataWrapper<MoonInfo>> {
+    Flex::row()
+        .with_flex_child(
+            Label::new(utils::localized_label(label))
+                .with_text_color(theme::FOREGROUND_DARK)
+                .align_right(),
+            1.0,
+        )
+        .with_default_spacer()
+        .with_flex_child(
+            Label::new(move |data: &DataWrapper<MoonInfo>, _: &Env| value(&data.0)).align_left(),
+            1.0,
+        )
+}
+
+fn crescent_row() -> impl Widget<DataWrapper<CrescentVisibility>> {
+    Flex::column()
+        .with_child(
+            Label::new(|data: &DataWrapper<CrescentVisibility>, _: &Env| {
+                format!(
+                    "Evening of {}",
+                    data.0.sunset.with_timezone(&Local).format("%a %d %b")
+                )
+            })
+            .with_font(theme::UI_FONT_BOLD),
+        )
+        .with_child(Label::new(
+            |data: &DataWrapper<CrescentVisibility>, _: &Env| {
+                format!(
+                    "Sunset {}, moonset {}, age {}",
+                    format_time(Some(data.0.sunset)),
+                    format_time(data.0.moonset),
+                    format_age(data.0.moon_age),
+                )
+            },
+        ))
+        .with_child(
+            Label::new(|data: &DataWrapper<CrescentVisibility>, _: &Env| {
+                format!(
+                    "{} (q = {:.3}), best at {}",
+                    data.0.class.description(),
+                    data.0.q,
+                    format_time(Some(data.0.best_time)),
+                )
+            })
+            .with_line_break_mode(druid::widget::LineBreaking::WordWrap)
+            .with_text_alignment(druid::TextAlignment::Center),
+        )
+        .padding(4.0)
+}
+
+pub fn moon_root() -> impl Widget<AppState> {
+    let phase = Flex::row()
+        .cross_axis_alignment(CrossAxisAlignment::Center)
+        .with_child(
+            ui_main::moon_painter()
+                .fix_size(48.0, 48.0)
+                .lens(lens_map_get(|data: &DataWrapper<MoonInfo>| {
+                    (data.0.illumination, data.0.waxing)
+                })),
+        )
+        .with_default_spacer()
+        .with_child(
+            Label::new(|data: &DataWrapper<MoonInfo>, _: &Env| data.0.phase_name().into())
+                .with_text_size(18.0),
+        );
+
+    let crescent = List::new(crescent_row).lens(lens_map_get(|data: &DataWrapper<MoonInfo>| {
+        std::sync::Arc::new(
+            data.0
+                .crescent
+                .iter()
+                .cloned()
+                .map(DataWrapper)
+                .collect::<Vec<_>>(),
+        )
+    }));
+
+    Flex::column()
+        .with_flex_spacer(1.0)
+        .with_child(phase)
+        .with_default_spacer()
+        .with_child(detail_row("Illumination", |moon| {
+            format!("{:.0}%", moon.illumination * 100.0)
+        }))
+        .with_child(detail_row("Age", |moon| format_age(moon.age)))
+        .with_child(detail_row("Moonrise", |moon| format_time(moon.rise)))
+        .with_child(detail_row("Moonset", |moon| format_time(moon.set)))
+        .with_child(detail_row("Last conjunction", |moon| {
+            format_date_time(moon.last_conjunction)
+        }))
+        .with_child(detail_row("Next conjunction", |moon| {
+            format_date_time(moon.next_conjunction)
+        }))
+        .with_default_spacer()
+        .with_child(crescent)
+        .with_flex_spacer(1.0)
+        .lens(AppState::lens_moon())
+        .center()
+        .padding(16.0)
+        .controller(ui_main::RootController)
+        .on_click(|ctx, _, _| ctx.window().close())
+        .env_scope(|env, app_state| {
+            app_state.config.apply_appearance_to_env(env);
+            env.set(theme::UI_FONT, env.get(theme::UI_FONT).with_size(12.0))
+        })
+}
@@ -0,0 +1,150 @@
+use chrono::{DateTime, Local, Utc};
+use druid::{
+    kurbo::{Circle, Line, Vec2},
+    piet::{Text, TextLayout, TextLayoutBuilder},
+    theme,
+    widget::{prelude::*, Flex, Label, Painter},
+    WidgetExt,
+};
+
+use crate::{
+    qibla::QiblaInfo,
+    utils::{localized_label, DataWrapper},
+    *,
+};
+
+const COMPASS_SIZE: f64 = 160.0;
+
+fn format_times(times: &[DateTime<Utc>]) -> String {
+    if times.is_empty() {
+        return "Not today".into();
+    }
+    times
+        .iter()
+        .map(|time| time.with_timezone(&Local).format("%I:%M %p").to_string())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn format_distance(distance: f64) -> String {
+    if distance < 1.0 {
+        format!("{:.0} m", distance * 1000.0)
+    } else {
+        format!("{:.0} km", distance)
+    }
+}
+
+/// A compass card with north up and a needle along the Qibla.
+fn compass_painter() -> Painter<f64> {
+    Painter::new(|ctx, &bearing: &f64, env| {
+        let size = ctx.size();
+        let center = size.to_rect().center();
+        let radius = size.width.min(size.height) / 2.0 - 1.0;
+        let dim = env.get(theme::FOREGROUND_DARK);
+
+        ctx.stroke(Circle::new(center, radius), &dim, 1.0);
+        // Bearings are clockwise from north, which is up.
+        let towards = |bearing: f64, length: f64| {
+            let angle = bearing.to_radians();
+            center + Vec2::new(angle.sin(), -angle.cos()) * length
+        };
+
+        for tick in (0..360).step_by(30) {
+            let inner = if tick % 90 == 0 { 0.8 } else { 0.88 };
+            ctx.stroke(
+                Line::new(
+                    towards(tick as _, radius * inner),
+                    towards(tick as _, radius),
+                ),
+                &dim,
+                1.0,
+            );
+        }
+        for (bearing, letter) in [(0.0, "N"), (90.0, "E"), (180.0, "S"), (270.0, "W")] {
+            let layout = ctx
+                .text()
+                .new_text_layout(letter)
+                .text_color(dim.clone())
+                .build()
+                .unwrap();
+            let at = towards(bearing, radius * 0.65) - layout.size().to_vec2() / 2.0;
+            ctx.draw_text(&layout, at);
+        }
+
+        let accent = env.get(theme::PRIMARY_LIGHT);
+        ctx.stroke(
+            Line::new(
+                towards(bearing + 180.0, radius * 0.3),
+                towards(bearing, radius * 0.9),
+            ),
+            &accent,
+            3.0,
+        );
+        ctx.fill(Circle::new(towards(bearing, radius * 0.9), 5.0), &accent);
+        ctx.fill(Circle::new(center, 3.0), &accent);
+    })
+}
+
+fn detail_row(
+    label: &'static str,
+    value: impl Fn(&QiblaInfo) -> String + 'static,
+) -> impl Widget<DataWrapper<QiblaInfo>> {
+    Flex::row()
+        .with_flex_child(
+            Label::new(localized_label(label))
+                .with_text_color(theme::FOREGROUND_DARK)
+                .align_right(),
+            1.0,
+        )
+        .with_default_spacer()
+        .with_flex_child(
+            Label::new(move |data: &DataWrapper<QiblaInfo>, _: &Env| value(&data.0)).align_left(),
+            1.0,
+        )
+}
+
+pub fn qibla_root() -> impl Widget<AppState> {
+    let title = Label::new(localized_label("Qibla")).with_text_size(18.0);
+    let compass = compass_painter()
+        .fix_size(COMPASS_SIZE, COMPASS_SIZE)
+        .lens(utils::lens_map_get(|data: &DataWrapper<QiblaInfo>| {
+            data.0.bearing
+        }));
+    let note = Label::new(localized_label(
+        "Bearings are from true north; a magnetic compass may differ by the local declination.",
+    ))
+    .with_text_color(theme::FOREGROUND_DARK)
+    .with_line_break_mode(druid::widget::LineBreaking::WordWrap)
+    .with_text_alignment(druid::TextAlignment::Center);
+
+    Flex::column()
+        .with_flex_spacer(1.0)
+        .with_child(title)
+        .with_default_spacer()
+        .with_child(compass)
+        .with_default_spacer()
+        .with_child(detail_row("Direction", |qibla| {
+            format!("{:.1}°", qibla.bearing)
+        }))
+        .with_child(detail_row("Distance", |qibla| {
+            format_distance(qibla.distance)
+        }))
+        .with_child(detail_row("Facing the sun", |qibla| {
+            format_times(&qibla.sun_ahead)
+        }))
+        .with_child(detail_row("Sun behind", |qibla| {
+            format_times(&qibla.sun_behind)
+        }))
+        .with_default_spacer()
+        .with_child(note)
+        .with_flex_spacer(1.0)
+        .lens(AppState::lens_qibla())
+        .center()
+        .padding(16.0)
+        .controller(ui_main::RootController)
+        .on_click(|ctx, _, _| ctx.window().close())
+        .env_scope(|env, app_state| {
+            app_state.config.apply_appearance_to_env(env);
+            env.set(theme::UI_FONT, env.get(theme::UI_FONT).with_size(12.0))
+        })
+}
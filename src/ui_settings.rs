@@ -1,3 +1,5 @@
+use std::path::PathBuf;
+
 use druid::{
     lens,
     lens::Identity,
@@ -5,14 +7,14 @@ use druid::{
     widget::{
         prelude::*, Checkbox, CrossAxisAlignment, Flex, Label, Parse, SizedBox, Switch, TextBox,
     },
-    LensExt, TextAlignment, WidgetExt,
+    LensExt, Target, TextAlignment, WidgetExt,
 };
 use druid_widget_nursery::{prism, DropdownSelect, MultiCheckbox, MultiRadio, TitleBar};
 use extract::try_extract;
 
-use crate::{utils::localized_label, widgets::button::Button, *};
+use crate::{hijri::HijriCalendar, utils::localized_label, widgets::button::Button, *};
 
-pub const WINDOW_SIZE: Size = Size::new(600.0, 750.0);
+pub const WINDOW_SIZE: Size = Size::new(600.0, 820.0);
 
 const SECTION_TITLE_SIZE: f64 = 15.0;
 const LABEL_COLUMN_WIDTH: f64 = 100.0;
@@ -39,35 +41,8 @@ pub fn settings_root() -> impl Widget<Config> {
         .with_flex_spacer(1.0)
         .with_child(Switch::new().lens(lens!(Config, dark_mode)));
 
-    let available_methods = {
-        use config::AvailableMethods::*;
-        [
-            (localized_label("Dubai"), Dubai),
-            (localized_label("Egyptian"), Egyptian),
-            (localized_label("Karachi"), Karachi),
-            (localized_label("Kuwait"), Kuwait),
-            (localized_label("Muslim World League"), MuslimWorldLeague),
-            (
-                localized_label("Islamic Society North America"),
-                NorthAmerica,
-            ),
-            (localized_label("Qatar"), Qatar),
-            (localized_label("Singapore"), Singapore),
-            (localized_label("Umm Al Qura University, Makkah"), UmmAlQura),
-            (
-                localized_label("Moonsighting Committee"),
-                MoonsightingCommittee,
-            ),
-            (
-                localized_label("Moonsighting Committee with red Isha"),
-                MoonsightingCommitteeRedIsha,
-            ),
-            (
-                localized_label("Moonsighting Committee with white Isha"),
-                MoonsightingCommitteeWhiteIsha,
-            ),
-        ]
-    };
+    let available_methods =
+        AVAILABLE_METHODS.map(|(label, method)| (localized_label(label), method));
 
     let calculation_grp_label = Label::new(localized_label("Calculation"))
         .with_text_alignment(TextAlignment::Start)
@@ -259,6 +234,534 @@ pub fn settings_root() -> impl Widget<Config> {
         ).with_flex_spacer(1.0)
         .lens(lens!(Config, asr));
 
+    let hijri_calendar = Flex::row()
+        .with_child(Label::new(localized_label("Hijri calendar")).fix_width(LABEL_COLUMN_WIDTH))
+        .with_flex_spacer(1.0)
+        .with_child(
+            DropdownSelect::new([
+                (localized_label("Umm al-Qura"), HijriCalendar::UmmAlQura),
+                (localized_label("Tabular"), HijriCalendar::Tabular),
+            ])
+            .lens(lens!(Config, hijri.calendar)),
+        );
+    let hijri_offset = Flex::row()
+        .with_child(Label::new(localized_label("Moon sighting")).fix_width(LABEL_COLUMN_WIDTH))
+        .with_default_spacer()
+        .with_child(Parse::new(
+            TextBox::new()
+                .with_text_alignment(TextAlignment::End)
+                .fix_width(LABEL_COLUMN_WIDTH / 2.0),
+        ))
+        .with_child(Label::new(|data: &Option<i8>, _: &Env| {
+            match *data {
+                None | Some(-1 | 0 | 1) => "day of correction",
+                _ => "days of correction",
+            }
+            .into()
+        }))
+        .with_flex_spacer(1.0)
+        .lens(Identity.map(
+            |config: &Config| Some(config.hijri.offset),
+            |config, input: Option<i8>| {
+                config.hijri.offset = input
+                    .unwrap_or_default()
+                    .clamp(-MAX_HIJRI_OFFSET, MAX_HIJRI_OFFSET)
+            },
+        ));
+
+    let events_grp_label = Label::new(localized_label("Events"))
+        .with_text_alignment(TextAlignment::Start)
+        .with_font(theme::UI_FONT_BOLD)
+        .with_text_size(SECTION_TITLE_SIZE)
+        .expand_width();
+    let mawlid = Flex::row()
+        .with_child(Label::new(localized_label("Mawlid")).fix_width(LABEL_COLUMN_WIDTH))
+        .with_flex_spacer(1.0)
+        .with_child(Switch::new().lens(lens!(Config, events.mawlid)));
+    let event_reminder = Flex::row()
+        .with_child(Label::new(localized_label("Reminder")).fix_width(LABEL_COLUMN_WIDTH))
+        .with_default_spacer()
+        .with_child(
+            MultiCheckbox::new(
+                "The evening before",
+                Flex::row()
+                    .with_child(
+                        Parse::new(
+                            TextBox::new()
+                                .with_text_alignment(TextAlignment::End)
+                                .fix_width(LABEL_COLUMN_WIDTH / 2.0),
+                        )
+                        .lens(Identity.map(
+                            |minutes: &u16| Some(*minutes),
+                            |minutes, input| *minutes = input.unwrap_or(DEFAULT_EVENT_REMINDER),
+                        )),
+                    )
+                    .with_child(Label::new(|minutes: &u16, _: &Env| {
+                        match minutes {
+                            0 | 1 => "minute after Maghrib",
+                            _ => "minutes after Maghrib",
+                        }
+                        .into()
+                    })),
+                DEFAULT_EVENT_REMINDER,
+            )
+            .with_space(0.0)
+            .with_indent(20.0)
+            .lens(lens!(Config, events.reminder)),
+        )
+        .with_flex_spacer(1.0);
+    let user_events = Flex::row()
+        .with_child(Label::new(localized_label("Own events")).fix_width(LABEL_COLUMN_WIDTH))
+        .with_default_spacer()
+        .with_child(
+            Button::new(localized_label("Edit events.toml")).on_click(|_, _, _| {
+                if let Err(e) = open::that(utils::events_path()) {
+                    tracing::error!("Failed to open events file: {:?}", e);
+                }
+            }),
+        )
+        .with_default_spacer()
+        .with_child(
+            Label::new(localized_label("takes effect after restart"))
+                .with_text_color(theme::FOREGROUND_DARK),
+        )
+        .with_flex_spacer(1.0);
+
+    let fasting_grp_label = Label::new(localized_label("Sunnah fasting"))
+        .with_text_alignment(TextAlignment::Start)
+        .with_font(theme::UI_FONT_BOLD)
+        .with_text_size(SECTION_TITLE_SIZE)
+        .expand_width();
+    let fasts = Flex::row()
+        .with_child(Label::new(localized_label("Remind for")).fix_width(LABEL_COLUMN_WIDTH))
+        .with_default_spacer()
+        .with_child(
+            Flex::column()
+                .cross_axis_alignment(CrossAxisAlignment::Start)
+                .with_child(
+                    Checkbox::new(localized_label("Mondays and Thursdays"))
+                        .lens(lens!(Config, fasting.mondays_thursdays)),
+                )
+                .with_child(
+                    Checkbox::new(localized_label("Ayyam al-Bid, 13th to 15th of the month"))
+                        .lens(lens!(Config, fasting.ayyam_al_bid)),
+                )
+                .with_child(
+                    Checkbox::new(localized_label("Day of Arafah"))
+                        .lens(lens!(Config, fasting.arafah)),
+                )
+                .with_child(
+                    Checkbox::new(localized_label("Ashura with Tasu'a"))
+                        .lens(lens!(Config, fasting.ashura)),
+                )
+                .with_child(
+                    Checkbox::new(localized_label("Six days of Shawwal"))
+                        .lens(lens!(Config, fasting.six_of_shawwal)),
+                ),
+        )
+        .with_flex_spacer(1.0);
+    let fasting_reminder = Flex::row()
+        .with_child(Label::new(localized_label("Remind at")).fix_width(LABEL_COLUMN_WIDTH))
+        .with_default_spacer()
+        .with_child(Parse::new(
+            TextBox::new()
+                .with_text_alignment(TextAlignment::End)
+                .fix_width(LABEL_COLUMN_WIDTH / 2.0),
+        ))
+        .with_child(Label::new(|data: &Option<u16>, _: &Env| {
+            match *data {
+                None | Some(0 | 1) => "minute after Isha the evening before",
+                _ => "minutes after Isha the evening before",
+            }
+            .into()
+        }))
+        .with_flex_spacer(1.0)
+        .lens(Identity.map(
+            |config: &Config| Some(config.fasting.after_isha),
+            |config, input| config.fasting.after_isha = input.unwrap_or(DEFAULT_FASTING_REMINDER),
+        ));
+
+    let travel = Flex::row()
+        .with_child(Label::new(localized_label("Travel mode")).fix_width(LABEL_COLUMN_WIDTH))
+        .with_default_spacer()
+        .with_child(
+            MultiCheckbox::new(
+                "Switch off automatically",
+                Flex::row()
+                    .with_child(Label::new(localized_label("after")))
+                    .with_child(
+                        Parse::new(
+                            TextBox::new()
+                                .with_text_alignment(TextAlignment::End)
+                                .fix_width(LABEL_COLUMN_WIDTH / 2.0),
+                        )
+                        .lens(Identity.map(
+                            |days: &u8| Some(*days),
+                            |days, input| *days = input.unwrap_or(DEFAULT_TRAVEL_DAYS),
+                        )),
+                    )
+                    .with_child(Label::new(|days: &u8, _: &Env| {
+                        match days {
+                            0 | 1 => "day",
+                            _ => "days",
+                        }
+                        .into()
+                    })),
+                DEFAULT_TRAVEL_DAYS,
+            )
+            .with_space(0.0)
+            .with_indent(20.0)
+            .lens(lens!(Config, travel.auto_off_after)),
+        )
+        .with_flex_spacer(1.0);
+
+    let notifications_grp_label = Label::new(localized_label("Notifications"))
+        .with_text_alignment(TextAlignment::Start)
+        .with_font(theme::UI_FONT_BOLD)
+        .with_text_size(SECTION_TITLE_SIZE)
+        .expand_width();
+    let notify_at_start =
+        per_prayer_checkboxes("At start").lens(lens!(Config, notifications.at_start));
+    let notify_before_start = Flex::row()
+        .with_child(Label::new(localized_label("Before start")).fix_width(LABEL_COLUMN_WIDTH))
+        .with_default_spacer()
+        .with_child(
+            Flex::column()
+                .cross_axis_alignment(CrossAxisAlignment::Start)
+                .with_child(offsets("Fajr").lens(lens!(PerPrayer<Offsets>, fajr)))
+                .with_child(offsets("Sunrise").lens(lens!(PerPrayer<Offsets>, sunrise)))
+                .with_child(offsets("Dhuhr").lens(lens!(PerPrayer<Offsets>, dhuhr)))
+                .with_child(offsets("Asr").lens(lens!(PerPrayer<Offsets>, asr)))
+                .with_child(offsets("Maghrib").lens(lens!(PerPrayer<Offsets>, maghrib)))
+                .with_child(offsets("Isha").lens(lens!(PerPrayer<Offsets>, isha)))
+                .with_child(offsets("Qiyam").lens(lens!(PerPrayer<Offsets>, qiyam))),
+        )
+        .with_flex_spacer(1.0)
+        .lens(lens!(Config, notifications.before_start));
+    let notify_critical =
+        per_prayer_checkboxes("When critical").lens(lens!(Config, notifications.critical));
+    let quiet = Flex::row()
+        .with_child(Label::new(localized_label("Quiet")).fix_width(LABEL_COLUMN_WIDTH))
+        .with_default_spacer()
+        .with_child(
+            Flex::column()
+                .cross_axis_alignment(CrossAxisAlignment::Start)
+                .with_child(
+                    Checkbox::new(localized_label("During the system's do-not-disturb"))
+                        .lens(lens!(Config, quiet.follow_do_not_disturb)),
+                )
+                .with_child(
+                    Label::new(|config: &Config, _: &Env| match config.quiet.hours.len() {
+                        0 => "No quiet hours set under [[quiet.hours]] in config.toml".into(),
+                        1 => "1 period of quiet hours set in config.toml".into(),
+                        n => format!("{} periods of quiet hours set in config.toml", n),
+                    })
+                    .with_text_color(theme::FOREGROUND_DARK),
+                ),
+        )
+        .with_flex_spacer(1.0);
+    let popup = Flex::row()
+        .with_child(Label::new(localized_label("Show window")).fix_width(LABEL_COLUMN_WIDTH))
+        .with_default_spacer()
+        .with_child(
+            Flex::column()
+                .cross_axis_alignment(CrossAxisAlignment::Start)
+                .with_child(
+                    Checkbox::new(localized_label("At start"))
+                        .lens(lens!(Config, notifications.popup.at_start)),
+                )
+                .with_child(
+                    Checkbox::new(localized_label("When critical"))
+                        .lens(lens!(Config, notifications.popup.at_critical)),
+                )
+                .with_child(
+                    MultiCheckbox::new(
+                        "Hide again",
+                        Flex::row()
+                            .with_child(Label::new(localized_label("after")))
+                            .with_child(
+                                Parse::new(
+                                    TextBox::new()
+                                        .with_text_alignment(TextAlignment::End)
+                                        .fix_width(LABEL_COLUMN_WIDTH / 2.0),
+                                )
+                                .lens(Identity.map(
+                                    |seconds: &u16| Some(*seconds),
+                                    |seconds, input| {
+                                        *seconds = input.unwrap_or(DEFAULT_POPUP_HIDE_AFTER)
+                                    },
+                                )),
+                            )
+                            .with_child(Label::new(|seconds: &u16, _: &Env| {
+                                match seconds {
+                                    0 | 1 => "second",
+                                    _ => "seconds",
+                                }
+                                .into()
+                            })),
+                        DEFAULT_POPUP_HIDE_AFTER,
+                    )
+                    .with_space(0.0)
+                    .with_indent(20.0)
+                    .lens(lens!(Config, notifications.popup.hide_after)),
+                )
+                .with_child(
+                    Label::new(localized_label(
+                        "Not while in do-not-disturb or a full-screen application",
+                    ))
+                    .with_text_color(theme::FOREGROUND_DARK),
+                ),
+        )
+        .with_flex_spacer(1.0);
+
+    let audio_grp_label = Label::new(localized_label("Sound"))
+        .with_text_alignment(TextAlignment::Start)
+        .with_font(theme::UI_FONT_BOLD)
+        .with_text_size(SECTION_TITLE_SIZE)
+        .expand_width();
+    let adhan_file = Flex::row()
+        .with_child(Label::new(localized_label("Adhan file")).fix_width(LABEL_COLUMN_WIDTH))
+        .with_default_spacer()
+        .with_flex_child(
+            file_path("WAV, OGG, MP3 or FLAC").lens(lens!(Config, audio.adhan)),
+            1.0,
+        );
+    let fajr_adhan_file = Flex::row()
+        .with_child(Label::new(localized_label("Fajr adhan file")).fix_width(LABEL_COLUMN_WIDTH))
+        .with_default_spacer()
+        .with_flex_child(
+            file_path("Same as the other adhan").lens(lens!(Config, audio.fajr_adhan)),
+            1.0,
+        );
+    let sounds = Flex::row()
+        .with_child(Label::new(localized_label("At start")).fix_width(LABEL_COLUMN_WIDTH))
+        .with_default_spacer()
+        .with_child(
+            Flex::column()
+                .cross_axis_alignment(CrossAxisAlignment::Start)
+                .with_child(sound("Fajr").lens(lens!(PerPrayer<Sound>, fajr)))
+                .with_child(sound("Sunrise").lens(lens!(PerPrayer<Sound>, sunrise)))
+                .with_child(sound("Dhuhr").lens(lens!(PerPrayer<Sound>, dhuhr)))
+                .with_child(sound("Asr").lens(lens!(PerPrayer<Sound>, asr)))
+                .with_child(sound("Maghrib").lens(lens!(PerPrayer<Sound>, maghrib)))
+                .with_child(sound("Isha").lens(lens!(PerPrayer<Sound>, isha)))
+                .with_child(sound("Qiyam").lens(lens!(PerPrayer<Sound>, qiyam))),
+        )
+        .with_flex_spacer(1.0)
+        .lens(lens!(Config, audio.at_start));
+    let speak = per_prayer_checkboxes("Announce").lens(lens!(Config, audio.speak));
+    let media = Flex::row()
+        .with_child(Label::new(localized_label("Media players")).fix_width(LABEL_COLUMN_WIDTH))
+        .with_default_spacer()
+        .with_child(
+            Flex::column()
+                .cross_axis_alignment(CrossAxisAlignment::Start)
+                .with_child(
+                    Checkbox::new(localized_label("Pause for the adhan and reminders"))
+                        .lens(lens!(Config, audio.pause_media)),
+                )
+                .with_child(
+                    Checkbox::new(localized_label("Resume afterwards"))
+                        .lens(lens!(Config, audio.resume_media)),
+                ),
+        )
+        .with_flex_spacer(1.0);
+    let volume = Flex::row()
+        .with_child(Label::new(localized_label("Volume")).fix_width(LABEL_COLUMN_WIDTH))
+        .with_default_spacer()
+        .with_child(
+            Parse::new(
+                TextBox::new()
+                    .with_text_alignment(TextAlignment::End)
+                    .fix_width(LABEL_COLUMN_WIDTH / 2.0),
+            )
+            .lens(Identity.map(
+                |config: &Config| Some(config.audio.volume),
+                |config, input: Option<u8>| {
+                    config.audio.volume = input.unwrap_or(DEFAULT_VOLUME).min(100)
+                },
+            )),
+        )
+        .with_child(Label::new("%"))
+        .with_flex_spacer(1.0);
+    let fade_in = Flex::row()
+        .with_child(Label::new(localized_label("Fade in")).fix_width(LABEL_COLUMN_WIDTH))
+        .with_default_spacer()
+        .with_child(Parse::new(
+            TextBox::new()
+                .with_text_alignment(TextAlignment::End)
+                .fix_width(LABEL_COLUMN_WIDTH / 2.0),
+        ))
+        .with_child(Label::new(|data: &Option<u8>, _: &Env| {
+            match *data {
+                None | Some(0 | 1) => "second",
+                _ => "seconds",
+            }
+            .into()
+        }))
+        .with_flex_spacer(1.0)
+        .lens(Identity.map(
+            |config: &Config| Some(config.audio.fade_in),
+            |config, input| config.audio.fade_in = input.unwrap_or(DEFAULT_FADE_IN),
+        ));
+
+    let alarm_grp_label = Label::new(localized_label("Fajr alarm"))
+        .with_text_alignment(TextAlignment::Start)
+        .with_font(theme::UI_FONT_BOLD)
+        .with_text_size(SECTION_TITLE_SIZE)
+        .expand_width();
+    let alarm_enabled = Flex::row()
+        .with_child(Label::new(localized_label("Enabled")).fix_width(LABEL_COLUMN_WIDTH))
+        .with_flex_spacer(1.0)
+        .with_child(Switch::new().lens(lens!(Config, fajr_alarm.enabled)));
+    let alarm_offset = Flex::row()
+        .with_child(Label::new(localized_label("Ring at")).fix_width(LABEL_COLUMN_WIDTH))
+        .with_default_spacer()
+        .with_child(Parse::new(
+            TextBox::new()
+                .with_text_alignment(TextAlignment::End)
+                .fix_width(LABEL_COLUMN_WIDTH / 2.0),
+        ))
+        .with_child(Label::new(|data: &Option<i16>, _: &Env| {
+            match *data {
+                None | Some(-1 | 0 | 1) => "minute after Fajr, negative for before",
+                _ => "minutes after Fajr, negative for before",
+            }
+            .into()
+        }))
+        .with_flex_spacer(1.0)
+        .lens(Identity.map(
+            |config: &Config| Some(config.fajr_alarm.offset),
+            |config, input| config.fajr_alarm.offset = input.unwrap_or_default(),
+        ));
+    let alarm_snooze = Flex::row()
+        .with_child(Label::new(localized_label("Snooze for")).fix_width(LABEL_COLUMN_WIDTH))
+        .with_default_spacer()
+        .with_child(Parse::new(
+            TextBox::new()
+                .with_text_alignment(TextAlignment::End)
+                .fix_width(LABEL_COLUMN_WIDTH / 2.0),
+        ))
+        .with_child(Label::new(|data: &Option<u8>, _: &Env| {
+            match *data {
+                None | Some(0 | 1) => "minute, but never past sunrise",
+                _ => "minutes, but never past sunrise",
+            }
+            .into()
+        }))
+        .with_flex_spacer(1.0)
+        .lens(Identity.map(
+            |config: &Config| Some(config.fajr_alarm.snooze),
+            |config, input| config.fajr_alarm.snooze = input.unwrap_or(DEFAULT_SNOOZE),
+        ));
+    let alarm_puzzle = Flex::row()
+        .with_child(Label::new(localized_label("Dismissing")).fix_width(LABEL_COLUMN_WIDTH))
+        .with_default_spacer()
+        .with_child(
+            Checkbox::new(localized_label("Needs a sum solved"))
+                .lens(lens!(Config, fajr_alarm.puzzle)),
+        )
+        .with_flex_spacer(1.0);
+
+    let calendar_grp_label = Label::new(localized_label("Calendar export"))
+        .with_text_alignment(TextAlignment::Start)
+        .with_font(theme::UI_FONT_BOLD)
+        .with_text_size(SECTION_TITLE_SIZE)
+        .expand_width();
+    let calendar_reminder = Flex::row()
+        .with_child(Label::new(localized_label("Reminder")).fix_width(LABEL_COLUMN_WIDTH))
+        .with_default_spacer()
+        .with_child(
+            MultiCheckbox::new(
+                "Before each prayer",
+                Flex::row()
+                    .with_child(
+                        Parse::new(
+                            TextBox::new()
+                                .with_text_alignment(TextAlignment::End)
+                                .fix_width(LABEL_COLUMN_WIDTH / 2.0),
+                        )
+                        .lens(Identity.map(
+                            |minutes: &u16| Some(*minutes),
+                            |minutes, input| *minutes = input.unwrap_or(DEFAULT_CALENDAR_REMINDER),
+                        )),
+                    )
+                    .with_child(Label::new(|minutes: &u16, _: &Env| {
+                        match minutes {
+                            0 | 1 => "minute before",
+                            _ => "minutes before",
+                        }
+                        .into()
+                    })),
+                DEFAULT_CALENDAR_REMINDER,
+            )
+            .with_space(0.0)
+            .with_indent(20.0)
+            .lens(lens!(Config, calendar.reminder)),
+        )
+        .with_flex_spacer(1.0);
+    let calendar_feed = Flex::row()
+        .with_child(Label::new(localized_label("Feed")).fix_width(LABEL_COLUMN_WIDTH))
+        .with_default_spacer()
+        .with_child(
+            MultiCheckbox::new(
+                "Serve on localhost",
+                Flex::row()
+                    .with_child(Label::new(localized_label("Port")))
+                    .with_default_spacer()
+                    .with_child(
+                        Parse::new(
+                            TextBox::new()
+                                .with_text_alignment(TextAlignment::End)
+                                .fix_width(LABEL_COLUMN_WIDTH / 2.0),
+                        )
+                        .lens(Identity.map(
+                            |port: &u16| Some(*port),
+                            // Port 0 would bind to a port chosen anew each time.
+                            |port, input| {
+                                *port = input.filter(|&port| port != 0).unwrap_or(DEFAULT_FEED_PORT)
+                            },
+                        )),
+                    ),
+                DEFAULT_FEED_PORT,
+            )
+            .with_space(0.0)
+            .with_indent(20.0)
+            .lens(lens!(Config, calendar.feed)),
+        )
+        .with_flex_spacer(1.0);
+
+    let print_grp_label = Label::new(localized_label("Printed timetable"))
+        .with_text_alignment(TextAlignment::Start)
+        .with_font(theme::UI_FONT_BOLD)
+        .with_text_size(SECTION_TITLE_SIZE)
+        .expand_width();
+    let print_paper = Flex::row()
+        .with_child(Label::new(localized_label("Paper")).fix_width(LABEL_COLUMN_WIDTH))
+        .with_flex_spacer(1.0)
+        .with_child(
+            DropdownSelect::new([
+                (localized_label("A4"), Paper::A4),
+                (localized_label("Letter"), Paper::Letter),
+            ])
+            .lens(lens!(Config, print.paper)),
+        )
+        .with_default_spacer()
+        .with_child(
+            DropdownSelect::new([
+                (localized_label("Portrait"), Orientation::Portrait),
+                (localized_label("Landscape"), Orientation::Landscape),
+            ])
+            .lens(lens!(Config, print.orientation)),
+        );
+    let print_logo = Flex::row()
+        .with_child(Label::new(localized_label("Logo")).fix_width(LABEL_COLUMN_WIDTH))
+        .with_default_spacer()
+        .with_flex_child(
+            file_path("PNG or JPEG, optional").lens(lens!(Config, print.logo)),
+            1.0,
+        );
+
     let adjustments_grp_label = Label::new(localized_label("User time adjustments"))
         .with_text_alignment(TextAlignment::Start)
         .with_font(theme::UI_FONT_BOLD)
@@ -271,11 +774,20 @@ pub fn settings_root() -> impl Widget<Config> {
     let adj_maghrib = adjustment("Maghrib").lens(lens!(Config, user_adjustments.maghrib));
     let adj_isha = adjustment("Isha").lens(lens!(Config, user_adjustments.isha));
 
-    let ok = Flex::row()
-        .with_flex_spacer(1.0)
-        .with_child(Button::new(localized_label("OK")).on_click(|ctx, _, _| ctx.window().close()));
+    let ok =
+        Flex::row()
+            .with_flex_spacer(1.0)
+            .with_child(Button::new(localized_label("OK")).on_click(
+                |ctx, data: &mut Config, _| {
+                    if let Err(e) = data.save() {
+                        tracing::error!("Failed to save config: {:?}", e);
+                    }
+                    ctx.submit_command(feed::APPLY.to(Target::Global));
+                    ctx.window().close()
+                },
+            ));
 
-    Flex::column()
+    let sections = Flex::column()
         .with_default_spacer()
         .with_child(appearance_grp_label)
         .with_default_spacer()
@@ -299,10 +811,108 @@ pub fn settings_root() -> impl Widget<Config> {
         .with_default_spacer()
         .with_child(asr)
         .with_default_spacer()
+        .with_child(hijri_calendar)
+        .with_default_spacer()
+        .with_child(hijri_offset)
+        .with_default_spacer()
+        .with_child(travel)
+        .with_default_spacer()
         // calculation done
         .with_default_spacer()
         //
         .with_default_spacer()
+        .with_child(events_grp_label)
+        .with_default_spacer()
+        .with_child(mawlid)
+        .with_default_spacer()
+        .with_child(event_reminder)
+        .with_default_spacer()
+        .with_child(user_events)
+        .with_default_spacer()
+        // events done
+        .with_default_spacer()
+        //
+        .with_default_spacer()
+        .with_child(fasting_grp_label)
+        .with_default_spacer()
+        .with_child(fasts)
+        .with_default_spacer()
+        .with_child(fasting_reminder)
+        .with_default_spacer()
+        // fasting done
+        .with_default_spacer()
+        //
+        .with_default_spacer()
+        .with_child(notifications_grp_label)
+        .with_default_spacer()
+        .with_child(notify_at_start)
+        .with_default_spacer()
+        .with_child(notify_before_start)
+        .with_default_spacer()
+        .with_child(notify_critical)
+        .with_default_spacer()
+        .with_child(popup)
+        .with_default_spacer()
+        .with_child(quiet)
+        .with_default_spacer()
+        // notifications done
+        .with_default_spacer()
+        //
+        .with_default_spacer()
+        .with_child(audio_grp_label)
+        .with_default_spacer()
+        .with_child(adhan_file)
+        .with_default_spacer()
+        .with_child(fajr_adhan_file)
+        .with_default_spacer()
+        .with_child(sounds)
+        .with_default_spacer()
+        .with_child(speak)
+        .with_default_spacer()
+        .with_child(volume)
+        .with_default_spacer()
+        .with_child(fade_in)
+        .with_default_spacer()
+        .with_child(media)
+        .with_default_spacer()
+        // sound done
+        .with_default_spacer()
+        //
+        .with_default_spacer()
+        .with_child(alarm_grp_label)
+        .with_default_spacer()
+        .with_child(alarm_enabled)
+        .with_default_spacer()
+        .with_child(alarm_offset)
+        .with_default_spacer()
+        .with_child(alarm_snooze)
+        .with_default_spacer()
+        .with_child(alarm_puzzle)
+        .with_default_spacer()
+        // fajr alarm done
+        .with_default_spacer()
+        //
+        .with_default_spacer()
+        .with_child(calendar_grp_label)
+        .with_default_spacer()
+        .with_child(calendar_reminder)
+        .with_default_spacer()
+        .with_child(calendar_feed)
+        .with_default_spacer()
+        // calendar export done
+        .with_default_spacer()
+        //
+        .with_default_spacer()
+        .with_child(print_grp_label)
+        .with_default_spacer()
+        .with_child(print_paper)
+        .with_default_spacer()
+        .with_child(print_logo)
+        .with_default_spacer()
+        // printed timetable done
+        .with_default_spacer()
+        //
+        .with_default_spacer()
         .with_child(adjustments_grp_label)
         .with_default_spacer()
         .with_child(adj_fajr)
@@ -318,13 +928,81 @@ pub fn settings_root() -> impl Widget<Config> {
         .with_child(adj_isha)
         .with_default_spacer()
         // user adjustments done
-        .with_flex_spacer(1.0)
-        .with_child(ok)
         .align_left()
+        .scroll()
+        .vertical();
+
+    Flex::column()
+        .with_flex_child(sections, 1.0)
+        .with_default_spacer()
+        .with_child(ok)
         .padding(16.0)
         .env_scope(|env, config| config.apply_appearance_to_env(env))
 }
 
+fn per_prayer_checkboxes(label: &'static str) -> Flex<PerPrayer<bool>> {
+    Flex::row()
+        .with_child(Label::new(localized_label(label)).fix_width(LABEL_COLUMN_WIDTH))
+        .with_default_spacer()
+        .with_child(Checkbox::new(localized_label("Fajr")).lens(lens!(PerPrayer<bool>, fajr)))
+        .with_child(Checkbox::new(localized_label("Sunrise")).lens(lens!(PerPrayer<bool>, sunrise)))
+        .with_child(Checkbox::new(localized_label("Dhuhr")).lens(lens!(PerPrayer<bool>, dhuhr)))
+        .with_child(Checkbox::new(localized_label("Asr")).lens(lens!(PerPrayer<bool>, asr)))
+        .with_child(Checkbox::new(localized_label("Maghrib")).lens(lens!(PerPrayer<bool>, maghrib)))
+        .with_child(Checkbox::new(localized_label("Isha")).lens(lens!(PerPrayer<bool>, isha)))
+        .with_child(Checkbox::new(localized_label("Qiyam")).lens(lens!(PerPrayer<bool>, qiyam)))
+        .with_flex_spacer(1.0)
+}
+
+fn offsets(prayer: &'static str) -> Flex<Offsets> {
+    Flex::row()
+        .with_child(Label::new(localized_label(prayer)).fix_width(LABEL_COLUMN_WIDTH * 0.8))
+        .with_child(
+            Parse::new(
+                TextBox::new()
+                    .with_placeholder("e.g. 20, 5")
+                    .with_text_alignment(TextAlignment::End)
+                    .fix_width(LABEL_COLUMN_WIDTH),
+            )
+            .lens(Identity.map(
+                |offsets: &Offsets| Some(offsets.clone()),
+                |offsets, input| *offsets = input.unwrap_or_default(),
+            )),
+        )
+        .with_default_spacer()
+        .with_child(Label::new(localized_label("minutes before")))
+}
+
+fn sound(prayer: &'static str) -> Flex<Sound> {
+    Flex::row()
+        .with_child(Label::new(localized_label(prayer)).fix_width(LABEL_COLUMN_WIDTH * 0.8))
+        .with_child(DropdownSelect::new([
+            (localized_label("Off"), Sound::Off),
+            (localized_label("Chime"), Sound::Chime),
+            (localized_label("Adhan"), Sound::Adhan),
+        ]))
+}
+
+fn file_path(placeholder: &'static str) -> impl Widget<Option<PathBuf>> {
+    TextBox::new()
+        .with_placeholder(placeholder)
+        .expand_width()
+        .lens(Identity.map(
+            |path: &Option<PathBuf>| {
+                path.as_ref()
+                    .map(|path| path.display().to_string())
+                    .unwrap_or_default()
+            },
+            |path, input: String| {
+                *path = if input.is_empty() {
+                    None
+                } else {
+                    Some(input.into())
+                }
+            },
+        ))
+}
+
 fn adjustment(prayer: &'static str) -> Flex<i64> {
     Flex::row()
         .with_child(Label::new(localized_label(prayer)).fix_width(LABEL_COLUMN_WIDTH))
@@ -0,0 +1,255 @@
+use chrono::{DateTime, Local, Utc};
+use druid::{
+    lens, theme,
+    widget::{
+        prelude::*, Controller, Either, Flex, Label, LabelText, List, Painter, SizedBox, TextBox,
+    },
+    FontWeight, Selector, WidgetExt,
+};
+use druid_widget_nursery::DropdownSelect;
+
+use crate::{
+    export::{self, Format, Span},
+    timetable::{self, Day, Row},
+    utils::{lens_map_get, localized_label},
+    widgets::button::Button,
+    *,
+};
+
+pub const WINDOW_SIZE: Size = Size::new(720.0, 720.0);
+
+const DATE_COLUMN_WIDTH: f64 = 90.0;
+const HIJRI_COLUMN_WIDTH: f64 = 150.0;
+const TIME_COLUMN_WIDTH: f64 = 72.0;
+
+fn format_time(time: DateTime<Utc>) -> String {
+    time.with_timezone(&Local).format("%I:%M %p").to_string()
+}
+
+fn cell<T: Data>(text: impl Into<LabelText<T>>, width: f64) -> impl Widget<T> {
+    Label::new(text).center().fix_width(width)
+}
+
+fn header_cell(label: &'static str, width: f64) -> impl Widget<AppState> {
+    Label::new(localized_label(label))
+        .with_text_color(theme::FOREGROUND_DARK)
+        .center()
+        .fix_width(width)
+}
+
+fn header() -> impl Widget<AppState> {
+    let mut header = Flex::row()
+        .with_child(header_cell("Date", DATE_COLUMN_WIDTH))
+        .with_child(header_cell("Hijri", HIJRI_COLUMN_WIDTH));
+    for prayer in ["Fajr", "Sunrise", "Dhuhr", "Asr", "Maghrib", "Isha"] {
+        header.add_child(header_cell(prayer, TIME_COLUMN_WIDTH));
+    }
+    header.padding(2.0)
+}
+
+fn day_row() -> impl Widget<Row> {
+    let time = |time: fn(&Day, AsrConfig) -> DateTime<Utc>| {
+        cell(
+            move |row: &Row, _: &Env| format_time(time(&row.0 .0, row.0 .2)),
+            TIME_COLUMN_WIDTH,
+        )
+    };
+
+    Flex::row()
+        .with_child(cell(
+            |row: &Row, _: &Env| row.0 .0.date.format("%a %d").to_string(),
+            DATE_COLUMN_WIDTH,
+        ))
+        .with_child(cell(
+            |row: &Row, _: &Env| {
+                let hijri = row.0 .0.hijri;
+                format!("{} {}", hijri.day, hijri.month_name())
+            },
+            HIJRI_COLUMN_WIDTH,
+        ))
+        .with_child(time(|day, _| day.fajr))
+        .with_child(time(|day, _| day.sunrise))
+        .with_child(time(|day, _| day.dhuhr))
+        .with_child(time(|day, asr| day.asr(asr)))
+        .with_child(time(|day, _| day.maghrib))
+        .with_child(time(|day, _| day.isha))
+        .padding(2.0)
+        .background(Painter::new(|ctx, row: &Row, env| {
+            if row.0 .1 {
+                ctx.fill(
+                    ctx.size()
+                        .to_rounded_rect(ui_main::size::ACTIVE_CORNER_RADIUS),
+                    &env.get(ui_main::color::REMAINING),
+                );
+            }
+        }))
+}
+
+pub fn export_button(format: Format) -> impl Widget<AppState> {
+    Button::new(format.extension().to_uppercase()).on_click(move |_, data: &mut AppState, _| {
+        match export::write_chosen(data, format) {
+            Ok(path) => {
+                if let Err(e) = open::that(path.parent().unwrap()) {
+                    tracing::error!("Failed to open exports folder: {:?}", e);
+                }
+            }
+            Err(e) => {
+                tracing::error!("Failed to export timetable: {:?}", e);
+                notification::show("Export failed", &e.to_string());
+            }
+        }
+    })
+}
+
+fn export_row() -> impl Widget<AppState> {
+    let custom = Flex::row()
+        .with_child(
+            TextBox::new()
+                .with_placeholder("YYYY-MM-DD")
+                .fix_width(100.0)
+                .lens(lens!(AppState, export_from)),
+        )
+        .with_child(Label::new("–"))
+        .with_child(
+            TextBox::new()
+                .with_placeholder("YYYY-MM-DD")
+                .fix_width(100.0)
+                .lens(lens!(AppState, export_to)),
+        );
+
+    Flex::row()
+        .with_child(Label::new(localized_label("Export")))
+        .with_default_spacer()
+        .with_child(
+            DropdownSelect::new([
+                (localized_label("Month"), Span::Month),
+                (localized_label("Year"), Span::Year),
+                (localized_label("Custom"), Span::Custom),
+            ])
+            .lens(lens!(AppState, export_span)),
+        )
+        .with_default_spacer()
+        .with_child(Either::new(
+            |data: &AppState, _: &Env| data.export_span == Span::Custom,
+            custom,
+            SizedBox::empty(),
+        ))
+        .with_default_spacer()
+        .with_child(export_button(Format::Csv))
+        .with_default_spacer()
+        .with_child(export_button(Format::Json))
+        .with_default_spacer()
+        .with_child(export_button(Format::Ics))
+        .with_default_spacer()
+        .with_child(export_button(Format::Html))
+        .with_default_spacer()
+        .with_child(export_button(Format::Pdf))
+}
+
+pub fn timetable_root() -> impl Widget<AppState> {
+    let month = Label::new(|data: &AppState, _: &Env| {
+        let (first, last) = timetable::month_bounds(data.timetable_month);
+        let hijri = timetable::hijri_span(data.hijri_date_on(first), data.hijri_date_on(last));
+        format!("{}, {}", data.timetable_month.format("%B %Y"), hijri)
+    })
+    .with_text_size(18.0);
+
+    let navigation =
+        Flex::row()
+            .with_child(Button::new("<").on_click(|_, data: &mut AppState, _| {
+                data.timetable_month = timetable::previous_month(data.timetable_month)
+            }))
+            .with_flex_child(month.center(), 1.0)
+            .with_child(Button::new(localized_label("Today")).on_click(
+                |_, data: &mut AppState, _| data.timetable_month = Local::today().naive_local(),
+            ))
+            .with_default_spacer()
+            .with_child(Button::new(">").on_click(|_, data: &mut AppState, _| {
+                data.timetable_month = timetable::next_month(data.timetable_month)
+            }));
+
+    let days = List::new(day_row)
+        .lens(lens_map_get(|data: &AppState| data.timetable.clone()))
+        .scroll()
+        .vertical();
+
+    let bottom = Flex::row()
+        .with_child(export_row())
+        .with_flex_spacer(1.0)
+        .with_child(
+            Button::from_label(
+                utils::Icon::Close
+                    .label(FontWeight::REGULAR)
+                    .with_text_size(16.0),
+            )
+            .on_click(|ctx, _, _| ctx.window().close()),
+        );
+
+    Flex::column()
+        .with_child(navigation)
+        .with_default_spacer()
+        .with_child(header())
+        .with_flex_child(days, 1.0)
+        .with_default_spacer()
+        .with_child(bottom)
+        .padding(16.0)
+        .controller(ui_main::RootController)
+        .controller(TimetableController)
+        .env_scope(|env, app_state| {
+            app_state.config.apply_appearance_to_env(env);
+            env.set(theme::UI_FONT, env.get(theme::UI_FONT).with_size(12.0))
+        })
+}
+
+const REFRESH: Selector = Selector::new("adhaan.timetable.refresh");
+
+/// Recalculates the days shown when the window opens and as the month, today or
+/// the configuration changes, rather than on every access.
+struct TimetableController;
+
+impl<W: Widget<AppState>> Controller<AppState, W> for TimetableController {
+    fn event(
+        &mut self,
+        child: &mut W,
+        ctx: &mut EventCtx,
+        event: &Event,
+        data: &mut AppState,
+        env: &Env,
+    ) {
+        match event {
+            Event::Command(command) if command.is(REFRESH) => data.refresh_timetable(),
+            _ => child.event(ctx, event, data, env),
+        }
+    }
+
+    fn lifecycle(
+        &mut self,
+        child: &mut W,
+        ctx: &mut LifeCycleCtx,
+        event: &LifeCycle,
+        data: &AppState,
+        env: &Env,
+    ) {
+        if let LifeCycle::WidgetAdded = event {
+            ctx.submit_command(REFRESH.to(ctx.widget_id()));
+        }
+        child.lifecycle(ctx, event, data, env)
+    }
+
+    fn update(
+        &mut self,
+        child: &mut W,
+        ctx: &mut UpdateCtx,
+        old_data: &AppState,
+        data: &AppState,
+        env: &Env,
+    ) {
+        if old_data.timetable_month != data.timetable_month
+            || old_data.date != data.date
+            || !old_data.config.same(&data.config)
+        {
+            ctx.submit_command(REFRESH.to(ctx.widget_id()));
+        }
+        child.update(ctx, old_data, data, env)
+    }
+}
@@ -1,5 +1,7 @@
 use std::path::PathBuf;
 
+use chrono::{DateTime, Duration, Local, LocalResult, NaiveDate, TimeZone, Utc};
+
 pub fn appdata_dir() -> PathBuf {
     let mut appdata_dir = if cfg!(debug_assertions) {
         PathBuf::new()
@@ -18,12 +20,66 @@ pub fn appdata_dir() -> PathBuf {
     appdata_dir
 }
 
+/// The first instant of `date` in the system time zone, past midnight when a
+/// transition skips it.
+pub fn local_midnight(date: NaiveDate) -> DateTime<Utc> {
+    let mut time = date.and_hms(0, 0, 0);
+    loop {
+        match Local.from_local_datetime(&time) {
+            LocalResult::Single(time) | LocalResult::Ambiguous(time, _) => {
+                return time.with_timezone(&Utc)
+            }
+            // Transitions happen on the minute.
+            LocalResult::None => time += Duration::minutes(1),
+        }
+    }
+}
+
+/// The localized strings, looked up next to the executable rather than in
+/// whatever directory the app is started from.
+pub fn i18n_dir() -> PathBuf {
+    let mut i18n_dir = if cfg!(debug_assertions) {
+        PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+    } else {
+        let mut exe_dir = std::env::current_exe().unwrap_or_default();
+        exe_dir.pop();
+        exe_dir
+    };
+    i18n_dir.push("resources");
+    i18n_dir.push("i18n");
+    i18n_dir
+}
+
 pub fn config_path() -> PathBuf {
     let mut config_path = appdata_dir();
     config_path.push("config.toml");
     config_path
 }
 
+pub fn events_path() -> PathBuf {
+    let mut events_path = appdata_dir();
+    events_path.push("events.toml");
+    events_path
+}
+
+pub fn prayed_path() -> PathBuf {
+    let mut prayed_path = appdata_dir();
+    prayed_path.push("prayed.toml");
+    prayed_path
+}
+
+pub fn webhooks_log_path() -> PathBuf {
+    let mut log_path = appdata_dir();
+    log_path.push("webhooks.log");
+    log_path
+}
+
+pub fn exports_dir() -> PathBuf {
+    let mut exports_dir = appdata_dir();
+    exports_dir.push("exports");
+    exports_dir
+}
+
 mod ui {
     use druid::{
         lens::Identity,
@@ -54,6 +110,10 @@ mod ui {
         Settings,
         Close,
         Info,
+        Travel,
+        Done,
+        Calendar,
+        Compass,
     }
 
     impl Icon {
@@ -70,6 +130,10 @@ mod ui {
                 // Icon::Close => "\u{ef2c}",
                 Icon::Close => "\u{e8bb}",
                 Icon::Info => "\u{e946}",
+                Icon::Travel => "\u{e709}",
+                Icon::Done => "\u{e73e}",
+                Icon::Calendar => "\u{e787}",
+                Icon::Compass => "\u{e879}",
             }
         }
 
@@ -86,6 +150,27 @@ mod ui {
                 .with_weight(weight),
             )
         }
+
+        /// Symbols most system fonts have, without the Segoe icon fonts.
+        #[cfg(not(windows))]
+        pub fn as_str(self) -> &'static str {
+            match self {
+                Icon::Location => "\u{25c9}",
+                Icon::Settings => "\u{2699}",
+                Icon::Close => "\u{2715}",
+                Icon::Info => "\u{24d8}",
+                Icon::Travel => "\u{2708}",
+                Icon::Done => "\u{2713}",
+                Icon::Calendar => "\u{25a6}",
+                Icon::Compass => "\u{2316}",
+            }
+        }
+
+        #[cfg(not(windows))]
+        pub fn label<T: Data>(self, weight: FontWeight) -> Label<T> {
+            Label::new(self.as_str())
+                .with_font(FontDescriptor::new(FontFamily::SYSTEM_UI).with_weight(weight))
+        }
     }
 }
 
@@ -0,0 +1,226 @@
+//! JSON posted to user URLs on prayer events, with every attempt recorded in
+//! webhooks.log in the app data dir.
+
+use std::{io::Write, path::Path, time::Duration};
+
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::Serialize;
+
+use crate::{config::WebhooksConfig, hooks::HookEvent};
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Payload {
+    pub event: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub prayer: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub label: Option<&'static str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub start: Option<DateTime<Utc>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub end: Option<DateTime<Utc>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub minutes_before: Option<u16>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub date: Option<NaiveDate>,
+    pub location: Option<String>,
+    pub latitude: f64,
+    pub longitude: f64,
+}
+
+const TIMEOUT: Duration = Duration::from_secs(10);
+/// Delay before the first retry, doubled for each one after.
+const FIRST_BACKOFF: Duration = Duration::from_secs(5);
+
+/// Posts `payload` to every URL subscribed to `event`, each on a thread of its own.
+pub fn post(config: &WebhooksConfig, event: HookEvent, payload: Payload) {
+    if !config.is_subscribed(event) {
+        return;
+    }
+
+    for url in &config.urls {
+        let url = url.clone();
+        let payload = payload.clone();
+        let retries = config.retries;
+        std::thread::spawn(move || {
+            deliver(
+                &url,
+                &payload,
+                retries,
+                FIRST_BACKOFF,
+                &crate::utils::webhooks_log_path(),
+            )
+        });
+    }
+}
+
+/// Posts `payload` to `url`, retrying failures after `backoff` doubled each
+/// time, and logs every attempt to `log_path`.
+fn deliver(url: &str, payload: &Payload, retries: u8, mut backoff: Duration, log_path: &Path) {
+    for attempt in 0..=retries {
+        let result = ureq::post(url).timeout(TIMEOUT).send_json(payload);
+        let outcome = match &result {
+            Ok(response) => response.status().to_string(),
+            Err(ureq::Error::Status(status, _)) => status.to_string(),
+            Err(e) => e.to_string(),
+        };
+        log(log_path, url, payload.event, attempt, &outcome);
+
+        match result {
+            // The listener will not change its mind about a bad request.
+            Ok(_) | Err(ureq::Error::Status(400..=499, _)) => return,
+            Err(_) if attempt < retries => {
+                std::thread::sleep(backoff);
+                backoff *= 2;
+            }
+            Err(_) => {}
+        }
+    }
+    tracing::error!("Gave up delivering `{}` to {}", payload.event, url);
+}
+
+fn log(path: &Path, url: &str, event: &str, attempt: u8, outcome: &str) {
+    let line = format!(
+        "{}\t{}\t{}\t{}\t{}\n",
+        Utc::now().to_rfc3339(),
+        event,
+        url,
+        attempt,
+        outcome
+    );
+    let result = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .and_then(|mut file| file.write_all(line.as_bytes()));
+    if let Err(e) = result {
+        tracing::error!("Failed to write webhooks log: {:?}", e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        io::{BufRead, BufReader, Read, Write},
+        net::TcpListener,
+        time::{Duration, Instant},
+    };
+
+    use chrono::TimeZone;
+
+    use super::*;
+
+    /// Accepts a request for each of `statuses`, answering with it, and returns
+    /// the bodies received.
+    fn receiver(statuses: &'static [u16]) -> (String, std::thread::JoinHandle<Vec<String>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/hook", listener.local_addr().unwrap());
+        let receiving = std::thread::spawn(move || {
+            statuses
+                .iter()
+                .map(|status| {
+                    let (stream, _) = listener.accept().unwrap();
+                    let mut reader = BufReader::new(stream);
+                    let mut length = 0;
+                    loop {
+                        let mut line = String::new();
+                        reader.read_line(&mut line).unwrap();
+                        if line == "\r\n" {
+                            break;
+                        }
+                        if let Some((name, value)) = line.split_once(':') {
+                            if name.eq_ignore_ascii_case("content-length") {
+                                length = value.trim().parse().unwrap();
+                            }
+                        }
+                    }
+                    let mut body = vec![0; length];
+                    reader.read_exact(&mut body).unwrap();
+                    write!(
+                        reader.get_mut(),
+                        "HTTP/1.1 {} Status\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+                        status
+                    )
+                    .unwrap();
+                    String::from_utf8(body).unwrap()
+                })
+                .collect()
+        });
+        (url, receiving)
+    }
+
+    fn payload() -> Payload {
+        Payload {
+            event: "waqt_start",
+            prayer: Some("Fajr".into()),
+            label: Some("Fajr"),
+            start: Some(Utc.ymd(2022, 10, 1).and_hms(0, 0, 0)),
+            end: Some(Utc.ymd(2022, 10, 1).and_hms(1, 0, 0)),
+            minutes_before: None,
+            date: None,
+            location: Some("Makkah".into()),
+            latitude: 21.42,
+            longitude: 39.83,
+        }
+    }
+
+    #[test]
+    fn retries_server_errors_with_backoff_and_logs_attempts() {
+        let (url, receiving) = receiver(&[503, 200]);
+        let log_path =
+            std::env::temp_dir().join(format!("adhaan-webhooks-test-{}.log", std::process::id()));
+        let _ = std::fs::remove_file(&log_path);
+
+        let backoff = Duration::from_millis(200);
+        let started = Instant::now();
+        deliver(&url, &payload(), 3, backoff, &log_path);
+        assert!(started.elapsed() >= backoff);
+
+        let bodies = receiving.join().unwrap();
+        assert_eq!(bodies.len(), 2);
+        for body in &bodies {
+            let json: serde_json::Value = serde_json::from_str(body).unwrap();
+            assert_eq!(json["event"], "waqt_start");
+            assert_eq!(json["prayer"], "Fajr");
+            assert_eq!(json["start"], "2022-10-01T00:00:00Z");
+            assert_eq!(json["location"], "Makkah");
+            // Fields not about the event are left out.
+            assert!(json.get("minutes_before").is_none());
+        }
+
+        let log = std::fs::read_to_string(&log_path).unwrap();
+        let attempts = log
+            .lines()
+            .map(|line| {
+                let fields = line.split('\t').collect::<Vec<_>>();
+                assert_eq!(fields[1], "waqt_start");
+                assert_eq!(fields[2], url);
+                (fields[3].to_string(), fields[4].to_string())
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            attempts,
+            [("0".into(), "503".into()), ("1".into(), "200".into())]
+        );
+        std::fs::remove_file(&log_path).unwrap();
+    }
+
+    #[test]
+    fn gives_up_on_client_errors() {
+        let (url, receiving) = receiver(&[404]);
+        let log_path = std::env::temp_dir().join(format!(
+            "adhaan-webhooks-test-client-{}.log",
+            std::process::id()
+        ));
+        let _ = std::fs::remove_file(&log_path);
+
+        deliver(&url, &payload(), 3, Duration::from_millis(10), &log_path);
+
+        assert_eq!(receiving.join().unwrap().len(), 1);
+        assert_eq!(
+            std::fs::read_to_string(&log_path).unwrap().lines().count(),
+            1
+        );
+        std::fs::remove_file(&log_path).unwrap();
+    }
+}